@@ -0,0 +1,300 @@
+use macroquad::prelude::*;
+use serde::Deserialize;
+use crate::consts::*;
+use crate::effects::*;
+use crate::events::{EventListener, GameEvent};
+use crate::profiles::{LifetimeStats, Profile};
+use crate::rules::{GameMode, Side};
+
+const ACHIEVEMENT_DATA: &str = include_str!("../data/achievements.toml");
+
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    Rally(u32),
+    Shutout(u32),
+    MaxSpeedReturn,
+    PointsInARow(u32),
+    Comeback(u32),
+    WinMode(String),
+    Wins(u32),
+    Matches(u32),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+#[derive(Deserialize)]
+struct AchievementList {
+    #[serde(default, rename = "achievement")]
+    achievements: Vec<Achievement>,
+}
+
+pub fn definitions() -> Vec<Achievement> {
+    match toml::from_str::<AchievementList>(ACHIEVEMENT_DATA) {
+        Ok(list) => list.achievements,
+        Err(err) => {
+            eprintln!("Skipping achievements: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+struct TrackedPlayer {
+    profile: usize,
+    side: Side,
+    unlocked: Vec<String>,
+    stats: LifetimeStats,
+}
+
+struct Toast {
+    title: String,
+    detail: String,
+    remaining: f32,
+}
+
+pub struct AchievementTracker {
+    definitions: Vec<Achievement>,
+    mode: GameMode,
+    rally: u32,
+    points: [u32; 4],
+    players: Vec<TrackedPlayer>,
+    streak: [u32; 4],
+    worst_deficit: [u32; 4],
+    unlocked: Vec<(usize, String)>,
+    toasts: Vec<Toast>,
+}
+
+impl AchievementTracker {
+    pub fn new(mode: GameMode) -> Self {
+        Self {
+            definitions: definitions(),
+            mode,
+            rally: 0,
+            points: [0; 4],
+            players: Vec::new(),
+            streak: [0; 4],
+            worst_deficit: [0; 4],
+            unlocked: Vec::new(),
+            toasts: Vec::new(),
+        }
+    }
+
+    pub fn add_player(&mut self, profile: usize, side: Side, source: &Profile) {
+        self.players.push(TrackedPlayer {
+            profile,
+            side,
+            unlocked: source.achievements.clone(),
+            stats: source.stats.clone(),
+        });
+    }
+
+    pub fn unlocked(&self) -> &[(usize, String)] {
+        &self.unlocked
+    }
+
+    fn check(&mut self, side: Option<Side>, test: impl Fn(&Condition, &TrackedPlayer) -> bool) {
+        for player in &mut self.players {
+            if side.is_some_and(|side| side != player.side) {
+                continue;
+            }
+            for achievement in &self.definitions {
+                if player.unlocked.contains(&achievement.id) || !test(&achievement.condition, player) {
+                    continue;
+                }
+                player.unlocked.push(achievement.id.clone());
+                self.unlocked.push((player.profile, achievement.id.clone()));
+                self.toasts.push(Toast {
+                    title: format!("ACHIEVEMENT UNLOCKED: {}", achievement.name),
+                    detail: achievement.description.clone(),
+                    remaining: ACHIEVEMENT_TOAST_TIME,
+                });
+            }
+        }
+    }
+
+    fn on_hit(&mut self, side: Side, speed: f32) {
+        self.rally += 1;
+        let rally = self.rally;
+        self.check(None, |condition, _| matches!(condition, Condition::Rally(target) if rally >= *target));
+        let at_max = speed >= BALL_MAX_SPEED - 1.0;
+        self.check(Some(side), |condition, _| at_max && *condition == Condition::MaxSpeedReturn);
+    }
+
+    fn on_point(&mut self, scorer: Side) {
+        self.points[scorer.index()] += 1;
+        let points = self.points;
+        for side in Side::ALL {
+            if side == scorer {
+                self.streak[side.index()] += 1;
+            } else {
+                self.streak[side.index()] = 0;
+            }
+            let behind = points[side.opponent().index()].saturating_sub(points[side.index()]);
+            self.worst_deficit[side.index()] = self.worst_deficit[side.index()].max(behind);
+        }
+        let streak = self.streak[scorer.index()];
+        self.check(Some(scorer), |condition, _| matches!(condition, Condition::PointsInARow(target) if streak >= *target));
+    }
+
+    fn on_match_end(&mut self, winner: Side) {
+        let mode = self.mode;
+        let loser_points = self.points[winner.opponent().index()];
+        let winner_points = self.points[winner.index()];
+        let deficit = self.worst_deficit[winner.index()];
+        let two_sided = mode != GameMode::FourPlayer;
+        self.check(Some(winner), |condition, player| match condition {
+            Condition::Shutout(target) => two_sided && loser_points == 0 && winner_points >= *target,
+            Condition::Comeback(target) => two_sided && deficit >= *target,
+            Condition::WinMode(label) => label == mode.label(),
+            Condition::Wins(target) => player.stats.total_wins() + 1 >= *target,
+            _ => false,
+        });
+        self.check(None, |condition, player| {
+            matches!(condition, Condition::Matches(target) if player.stats.matches + 1 >= *target)
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if let Some(toast) = self.toasts.first_mut() {
+            toast.remaining -= dt;
+            if toast.remaining <= 0.0 {
+                self.toasts.remove(0);
+            }
+        }
+    }
+
+    pub fn draw(&self, phase: f32) {
+        let Some(toast) = self.toasts.first() else {
+            return;
+        };
+        let slide = (ACHIEVEMENT_TOAST_TIME - toast.remaining).min(toast.remaining).min(0.3) / 0.3;
+        let width = 460.0;
+        let height = 58.0;
+        let x = SCREEN_WIDTH / 2.0 - width / 2.0;
+        let y = -height + (height + 70.0) * slide;
+        let color = get_rainbow_color((phase * 2.0) % 360.0);
+
+        draw_rectangle(x, y, width, height, Color::new(0.0, 0.0, 0.0, 0.85));
+        draw_rectangle_lines(x, y, width, height, 3.0, color);
+        let title_size = 20.0;
+        draw_text_ex(
+            &toast.title,
+            SCREEN_WIDTH / 2.0 - measure_text(&toast.title, None, title_size as u16, 1.0).width / 2.0,
+            y + 24.0,
+            TextParams {
+                font: None,
+                font_size: title_size as u16,
+                color,
+                ..Default::default()
+            },
+        );
+        let detail_size = 16.0;
+        draw_text_ex(
+            &toast.detail,
+            SCREEN_WIDTH / 2.0 - measure_text(&toast.detail, None, detail_size as u16, 1.0).width / 2.0,
+            y + 46.0,
+            TextParams {
+                font: None,
+                font_size: detail_size as u16,
+                color: Color::new(0.85, 0.85, 0.85, 1.0),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+impl EventListener for AchievementTracker {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::Serve { .. } => self.rally = 0,
+            GameEvent::PaddleHit { side, speed, .. } => self.on_hit(side, speed),
+            GameEvent::Score { scorer, .. } => self.on_point(scorer),
+            GameEvent::MatchEnd { winner } => self.on_match_end(winner),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(mode: GameMode) -> AchievementTracker {
+        let mut tracker = AchievementTracker::new(mode);
+        tracker.add_player(0, Side::Left, &Profile::new("LEFT".to_string(), 0.0));
+        tracker.add_player(1, Side::Right, &Profile::new("RIGHT".to_string(), 180.0));
+        tracker
+    }
+
+    fn hit(side: Side, speed: f32) -> GameEvent {
+        GameEvent::PaddleHit { side, position: Vec2::ZERO, hue: 0.0, speed, offset: 0.0 }
+    }
+
+    fn score(tracker: &mut AchievementTracker, scorer: Side, times: u32) {
+        for _ in 0..times {
+            tracker.on_event(&GameEvent::Score { scorer, server: scorer });
+        }
+    }
+
+    fn unlocked_by(tracker: &AchievementTracker, profile: usize) -> Vec<&str> {
+        tracker.unlocked().iter().filter(|(owner, _)| *owner == profile).map(|(_, id)| id.as_str()).collect()
+    }
+
+    #[test]
+    fn bundled_definitions_parse_with_unique_ids() {
+        let definitions = definitions();
+        assert!(!definitions.is_empty());
+        for (i, achievement) in definitions.iter().enumerate() {
+            assert!(definitions[..i].iter().all(|other| other.id != achievement.id), "{}", achievement.id);
+        }
+    }
+
+    #[test]
+    fn a_long_rally_unlocks_once_for_every_player() {
+        let mut tracker = tracker(GameMode::Classic);
+        tracker.on_event(&GameEvent::Serve { server: Side::Left });
+        for i in 0..12 {
+            tracker.on_event(&hit(if i % 2 == 0 { Side::Left } else { Side::Right }, 300.0));
+        }
+        assert_eq!(unlocked_by(&tracker, 0), ["rally_10"]);
+        assert_eq!(unlocked_by(&tracker, 1), ["rally_10"]);
+
+        tracker.on_event(&GameEvent::Serve { server: Side::Right });
+        tracker.on_event(&hit(Side::Right, BALL_MAX_SPEED));
+        assert_eq!(unlocked_by(&tracker, 1), ["rally_10", "max_speed"]);
+        assert_eq!(unlocked_by(&tracker, 0), ["rally_10"]);
+    }
+
+    #[test]
+    fn streaks_shutouts_and_first_wins_go_to_the_winner() {
+        let mut tracker = tracker(GameMode::Classic);
+        score(&mut tracker, Side::Left, 7);
+        assert_eq!(unlocked_by(&tracker, 0), ["streak_5"]);
+        tracker.on_event(&GameEvent::MatchEnd { winner: Side::Left });
+
+        let left = unlocked_by(&tracker, 0);
+        assert!(left.contains(&"shutout_7") && left.contains(&"first_win"), "{:?}", left);
+        let right = unlocked_by(&tracker, 1);
+        assert!(!right.contains(&"first_win") && !right.contains(&"shutout_7"), "{:?}", right);
+    }
+
+    #[test]
+    fn winning_from_four_down_is_a_comeback() {
+        let mut tracker = tracker(GameMode::Classic);
+        score(&mut tracker, Side::Right, 4);
+        for _ in 0..5 {
+            score(&mut tracker, Side::Left, 1);
+            score(&mut tracker, Side::Right, 1);
+        }
+        score(&mut tracker, Side::Left, 4);
+        tracker.on_event(&GameEvent::MatchEnd { winner: Side::Left });
+        assert!(unlocked_by(&tracker, 0).contains(&"comeback_4"));
+        assert!(!unlocked_by(&tracker, 1).contains(&"comeback_4"));
+    }
+}
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Expert];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "EASY",
+            Difficulty::Normal => "NORMAL",
+            Difficulty::Hard => "HARD",
+            Difficulty::Expert => "EXPERT",
+        }
+    }
+
+    pub fn speed_factor(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.6,
+            Difficulty::Normal => 0.85,
+            Difficulty::Hard => 1.0,
+            Difficulty::Expert => 1.15,
+        }
+    }
+
+    pub fn dead_zone(self) -> f32 {
+        match self {
+            Difficulty::Easy => 35.0,
+            Difficulty::Normal => 20.0,
+            Difficulty::Hard => 12.0,
+            Difficulty::Expert => 6.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Personality {
+    Steady,
+    Aggressive,
+    Defensive,
+    Trickster,
+}
+
+impl Personality {
+    pub fn label(self) -> &'static str {
+        match self {
+            Personality::Steady => "STEADY",
+            Personality::Aggressive => "AGGRESSIVE",
+            Personality::Defensive => "DEFENSIVE",
+            Personality::Trickster => "TRICKSTER",
+        }
+    }
+
+    pub fn lean(self, time: f32) -> f32 {
+        match self {
+            Personality::Aggressive => 0.3,
+            Personality::Trickster => (time * 1.7).sin() * 0.4,
+            Personality::Steady | Personality::Defensive => 0.0,
+        }
+    }
+
+    pub fn presses_forward(self) -> bool {
+        self == Personality::Aggressive
+    }
+
+    pub fn recenters(self) -> bool {
+        self == Personality::Defensive
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AiStyle {
+    pub difficulty: Difficulty,
+    pub personality: Personality,
+}
+
+impl AiStyle {
+    pub fn standard() -> Self {
+        Self {
+            difficulty: Difficulty::Normal,
+            personality: Personality::Steady,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!("{} {}", self.difficulty.label(), self.personality.label())
+    }
+}
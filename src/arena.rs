@@ -0,0 +1,434 @@
+use std::sync::OnceLock;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::consts::*;
+use crate::effects::*;
+use crate::ball::{Ball, CollisionResult};
+use crate::storage;
+
+const BUILTIN_ARENAS: [&str; 4] = [
+    include_str!("../arenas/classic.toml"),
+    include_str!("../arenas/pillars.toml"),
+    include_str!("../arenas/windmill.toml"),
+    include_str!("../arenas/fortress.toml"),
+];
+
+fn builtin_arenas() -> &'static [Arena] {
+    static ARENAS: OnceLock<Vec<Arena>> = OnceLock::new();
+    ARENAS.get_or_init(|| BUILTIN_ARENAS.iter().filter_map(|source| Arena::parse(source).ok()).collect())
+}
+
+fn slug(name: &str) -> String {
+    let slug: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if slug.is_empty() { "custom".to_string() } else { slug }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Shape {
+    Rect,
+    Circle,
+    Bar,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Motion {
+    pub dx: f32,
+    pub dy: f32,
+    pub period: f32,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub shape: Shape,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub width: f32,
+    #[serde(default)]
+    pub height: f32,
+    #[serde(default)]
+    pub radius: f32,
+    #[serde(default)]
+    pub angle: f32,
+    #[serde(default)]
+    pub spin: f32,
+    #[serde(default)]
+    pub motion: Option<Motion>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Spawn {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Arena {
+    pub name: String,
+    #[serde(default = "full_goal")]
+    pub goal_size: f32,
+    #[serde(default, rename = "spawn")]
+    pub spawns: Vec<Spawn>,
+    #[serde(default, rename = "obstacle")]
+    pub obstacles: Vec<Obstacle>,
+}
+
+fn full_goal() -> f32 {
+    SCREEN_HEIGHT
+}
+
+impl Obstacle {
+    pub fn center(&self, time: f32) -> Vec2 {
+        let base = Vec2::new(self.x, self.y);
+        match self.motion {
+            Some(motion) if motion.period > 0.0 => {
+                let wave = (time / motion.period * std::f32::consts::TAU).sin();
+                base + Vec2::new(motion.dx, motion.dy) * wave
+            }
+            _ => base,
+        }
+    }
+
+    pub fn rotation(&self, time: f32) -> f32 {
+        (self.angle + self.spin * time).to_radians()
+    }
+
+    pub fn half_extents(&self) -> Vec2 {
+        Vec2::new(self.width / 2.0, self.height / 2.0)
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        let offset = point - Vec2::new(self.x, self.y);
+        match self.shape {
+            Shape::Circle => offset.length() <= self.radius,
+            Shape::Rect | Shape::Bar => {
+                let rotation = if self.shape == Shape::Bar { self.angle.to_radians() } else { 0.0 };
+                let axis = Vec2::from_angle(rotation);
+                let local = Vec2::new(offset.dot(axis), offset.dot(axis.perp()));
+                let half = self.half_extents().max(Vec2::splat(6.0));
+                local.x.abs() <= half.x && local.y.abs() <= half.y
+            }
+        }
+    }
+
+    pub fn resize_handle(&self) -> Vec2 {
+        let center = Vec2::new(self.x, self.y);
+        match self.shape {
+            Shape::Circle => center + Vec2::new(self.radius, 0.0),
+            Shape::Rect => center + self.half_extents(),
+            Shape::Bar => center + Vec2::from_angle(self.angle.to_radians()) * self.width / 2.0,
+        }
+    }
+
+    pub fn resize_to(&mut self, handle: Vec2) {
+        let offset = handle - Vec2::new(self.x, self.y);
+        match self.shape {
+            Shape::Circle => self.radius = offset.length().max(MIN_OBSTACLE_SIZE / 2.0),
+            Shape::Rect => {
+                self.width = (offset.x * 2.0).max(MIN_OBSTACLE_SIZE);
+                self.height = (offset.y * 2.0).max(MIN_OBSTACLE_SIZE);
+            }
+            Shape::Bar => {
+                self.width = (offset.length() * 2.0).max(MIN_OBSTACLE_SIZE);
+                self.angle = offset.y.atan2(offset.x).to_degrees();
+            }
+        }
+    }
+
+    pub fn scale(&mut self, factor: f32) {
+        match self.shape {
+            Shape::Circle => self.radius = (self.radius * factor).max(MIN_OBSTACLE_SIZE / 2.0),
+            Shape::Rect => {
+                self.width = (self.width * factor).max(MIN_OBSTACLE_SIZE);
+                self.height = (self.height * factor).max(MIN_OBSTACLE_SIZE);
+            }
+            Shape::Bar => self.width = (self.width * factor).max(MIN_OBSTACLE_SIZE),
+        }
+    }
+
+    fn collide(&self, time: f32, ball: &mut Ball) -> bool {
+        let center = self.center(time);
+        let radius = BALL_SIZE / 2.0;
+
+        let (normal, depth) = match self.shape {
+            Shape::Circle => {
+                let offset = ball.position - center;
+                let distance = offset.length();
+                if distance >= self.radius + radius {
+                    return false;
+                }
+                (offset.try_normalize().unwrap_or(Vec2::X), self.radius + radius - distance)
+            }
+            Shape::Rect | Shape::Bar => {
+                let rotation = if self.shape == Shape::Bar { self.rotation(time) } else { 0.0 };
+                let axis = Vec2::from_angle(rotation);
+                let cross_axis = axis.perp();
+                let offset = ball.position - center;
+                let local_offset = Vec2::new(offset.dot(axis), offset.dot(cross_axis));
+                let half = self.half_extents();
+                let closest = local_offset.clamp(-half, half);
+                let separation = local_offset - closest;
+                let distance = separation.length();
+
+                let (local_normal, depth) = if distance > 0.0 {
+                    if distance >= radius {
+                        return false;
+                    }
+                    (separation / distance, radius - distance)
+                } else {
+                    let push_x = half.x - local_offset.x.abs();
+                    let push_y = half.y - local_offset.y.abs();
+                    if push_x < push_y {
+                        (Vec2::new(local_offset.x.signum(), 0.0), push_x + radius)
+                    } else {
+                        (Vec2::new(0.0, local_offset.y.signum()), push_y + radius)
+                    }
+                };
+                (axis * local_normal.x + cross_axis * local_normal.y, depth)
+            }
+        };
+
+        ball.position += normal * depth;
+        let approach = ball.velocity.dot(normal);
+        if approach < 0.0 {
+            ball.velocity -= normal * approach * 2.0;
+            ball.limit_angle();
+        }
+        true
+    }
+
+    pub fn draw(&self, time: f32, hue: f32) {
+        let center = self.center(time);
+        let color = get_rainbow_color(hue % 360.0);
+        let fill = Color::new(color.r * 0.35, color.g * 0.35, color.b * 0.35, 0.9);
+
+        match self.shape {
+            Shape::Circle => {
+                draw_glow(center, self.radius, color, 0.6);
+                draw_circle(center.x, center.y, self.radius, fill);
+                draw_circle_lines(center.x, center.y, self.radius, 3.0, color);
+            }
+            Shape::Rect => {
+                let half = self.half_extents();
+                draw_rectangle(center.x - half.x, center.y - half.y, self.width, self.height, fill);
+                draw_rectangle_lines(center.x - half.x, center.y - half.y, self.width, self.height, 3.0, color);
+            }
+            Shape::Bar => {
+                let axis = Vec2::from_angle(self.rotation(time));
+                let start = center - axis * self.width / 2.0;
+                let end = center + axis * self.width / 2.0;
+                draw_glow(center, self.height, color, 0.6);
+                draw_line(start.x, start.y, end.x, end.y, self.height, fill);
+                draw_line(start.x, start.y, end.x, end.y, self.height * 0.4, color);
+                draw_circle(center.x, center.y, self.height * 0.6, color);
+            }
+        }
+    }
+}
+
+impl Arena {
+    pub fn classic() -> Self {
+        Self {
+            name: "Classic".to_string(),
+            goal_size: SCREEN_HEIGHT,
+            spawns: Vec::new(),
+            obstacles: Vec::new(),
+        }
+    }
+
+    pub fn parse(source: &str) -> Result<Arena, String> {
+        toml::from_str(source).map_err(|err| err.to_string())
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("{}.toml", slug(&self.name))
+    }
+
+    pub fn is_builtin(name: &str) -> bool {
+        builtin_arenas().iter().any(|arena| slug(&arena.name) == slug(name))
+    }
+
+    pub fn save(&self) -> Result<String, String> {
+        if Arena::is_builtin(&self.name) {
+            return Err(format!("{} is a built-in arena, rename it first", self.name));
+        }
+        let file_name = format!("{}/{}", ARENA_DIR, self.file_name());
+        storage::save(&file_name, self)?;
+        Ok(storage::save_path(&file_name).display().to_string())
+    }
+
+    pub fn load_all() -> Vec<Arena> {
+        let mut arenas = builtin_arenas().to_vec();
+
+        let Ok(entries) = std::fs::read_dir(storage::save_path(ARENA_DIR)) else {
+            return arenas;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let loaded = std::fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|source| Arena::parse(&source));
+            match loaded {
+                Ok(arena) if Arena::is_builtin(&arena.name) => {
+                    eprintln!("Skipping arena {}: {} is a built-in arena", path.display(), arena.name);
+                }
+                Ok(arena) => match arenas.iter_mut().find(|known| known.name == arena.name) {
+                    Some(known) => *known = arena,
+                    None => arenas.push(arena),
+                },
+                Err(err) => eprintln!("Skipping arena {}: {}", path.display(), err),
+            }
+        }
+
+        arenas
+    }
+
+    pub fn goal_range(&self) -> (f32, f32) {
+        let half = self.goal_size.min(SCREEN_HEIGHT) / 2.0;
+        (SCREEN_HEIGHT / 2.0 - half, SCREEN_HEIGHT / 2.0 + half)
+    }
+
+    pub fn spawn_point(&self) -> Vec2 {
+        if self.spawns.is_empty() {
+            return Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0);
+        }
+        let spawn = self.spawns[macroquad::rand::gen_range(0, self.spawns.len())];
+        Vec2::new(spawn.x, spawn.y)
+    }
+
+    pub fn collide(&self, time: f32, ball: &mut Ball) -> Option<CollisionResult> {
+        let mut hit = false;
+        for obstacle in &self.obstacles {
+            hit |= obstacle.collide(time, ball);
+        }
+
+        let (goal_top, goal_bottom) = self.goal_range();
+        let outside_goal = ball.position.y < goal_top || ball.position.y > goal_bottom;
+        let radius = BALL_SIZE / 2.0;
+        if outside_goal && ball.position.x < radius && ball.velocity.x < 0.0 {
+            ball.position.x = radius;
+            ball.velocity.x = -ball.velocity.x;
+            hit = true;
+        }
+        if outside_goal && ball.position.x > SCREEN_WIDTH - radius && ball.velocity.x > 0.0 {
+            ball.position.x = SCREEN_WIDTH - radius;
+            ball.velocity.x = -ball.velocity.x;
+            hit = true;
+        }
+
+        hit.then_some(CollisionResult {
+            position: ball.position,
+            hue: ball.hue,
+        })
+    }
+
+    pub fn draw(&self, time: f32, phase: f32) {
+        for (i, obstacle) in self.obstacles.iter().enumerate() {
+            obstacle.draw(time, phase + i as f32 * 40.0);
+        }
+
+        let (goal_top, goal_bottom) = self.goal_range();
+        if goal_top > 0.0 {
+            let color = get_rainbow_color((phase + 300.0) % 360.0);
+            for x in [0.0, SCREEN_WIDTH - 8.0] {
+                draw_rectangle(x, 0.0, 8.0, goal_top, color);
+                draw_rectangle(x, goal_bottom, 8.0, SCREEN_HEIGHT - goal_bottom, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_arena_names_are_read_only() {
+        assert!(Arena::is_builtin("Classic"));
+        assert!(Arena::is_builtin("classic"));
+        assert!(!Arena::is_builtin("My Court"));
+        let err = Arena { name: "CLASSIC".to_string(), ..Arena::classic() }.save().unwrap_err();
+        assert!(err.contains("built-in"), "{}", err);
+    }
+
+    #[test]
+    fn built_in_arenas_parse_with_defaults() {
+        assert_eq!(builtin_arenas().len(), BUILTIN_ARENAS.len());
+
+        let windmill = Arena::parse(include_str!("../arenas/windmill.toml")).unwrap();
+        assert_eq!(windmill.goal_size, 420.0);
+        assert_eq!(windmill.spawns.len(), 2);
+        assert_eq!(windmill.obstacles.len(), 3);
+        let bar = &windmill.obstacles[0];
+        assert_eq!((bar.shape, bar.spin, bar.angle, bar.motion), (Shape::Bar, 60.0, 0.0, None));
+        let circle = &windmill.obstacles[1];
+        assert_eq!(circle.motion, Some(Motion { dx: 0.0, dy: 180.0, period: 5.0 }));
+        assert!((circle.center(1.25) - Vec2::new(250.0, 480.0)).length() < 0.01);
+
+        let pillars = Arena::parse(include_str!("../arenas/pillars.toml")).unwrap();
+        assert_eq!(pillars.goal_size, SCREEN_HEIGHT);
+        assert_eq!(pillars.goal_range(), (0.0, SCREEN_HEIGHT));
+    }
+
+    #[test]
+    fn rejects_unknown_obstacle_shapes() {
+        let err = Arena::parse("name = \"Odd\"\n[[obstacle]]\nshape = \"star\"\nx = 1.0\ny = 2.0\n").unwrap_err();
+        assert!(err.contains("star"), "{}", err);
+    }
+
+    #[test]
+    fn circles_push_the_ball_out_and_reflect_it() {
+        let arena = Arena::parse("name = \"Dot\"\n[[obstacle]]\nshape = \"circle\"\nx = 400.0\ny = 300.0\nradius = 40.0\n").unwrap();
+        let mut ball = Ball::new();
+        ball.position = Vec2::new(400.0 - 40.0 - BALL_SIZE / 2.0 + 3.0, 300.0);
+        ball.velocity = Vec2::new(400.0, 0.0);
+
+        assert!(arena.collide(0.0, &mut ball).is_some());
+        assert!((ball.position.x - (400.0 - 40.0 - BALL_SIZE / 2.0)).abs() < 0.01);
+        assert_eq!(ball.velocity, Vec2::new(-400.0, 0.0));
+
+        ball.position = Vec2::new(200.0, 300.0);
+        assert!(arena.collide(0.0, &mut ball).is_none());
+    }
+
+    #[test]
+    fn narrow_goals_wall_off_the_rest_of_the_goal_line() {
+        let arena = Arena { goal_size: 200.0, ..Arena::classic() };
+        let mut ball = Ball::new();
+        ball.position = Vec2::new(2.0, 100.0);
+        ball.velocity = Vec2::new(-300.0, 50.0);
+        assert!(arena.collide(0.0, &mut ball).is_some());
+        assert_eq!(ball.velocity, Vec2::new(300.0, 50.0));
+
+        ball.position = Vec2::new(2.0, 300.0);
+        ball.velocity = Vec2::new(-300.0, 50.0);
+        assert!(arena.collide(0.0, &mut ball).is_none());
+    }
+
+    #[test]
+    fn obstacle_bounces_keep_the_ball_within_the_paddle_angle() {
+        let arena = Arena::parse(
+            "name = \"Slab\"\n[[obstacle]]\nshape = \"rect\"\nx = 400.0\ny = 300.0\nwidth = 100.0\nheight = 20.0\n",
+        )
+        .unwrap();
+        let mut ball = Ball::new();
+        ball.position = Vec2::new(400.0, 300.0 - 10.0 - BALL_SIZE / 2.0 + 2.0);
+        ball.velocity = Vec2::new(30.0, 300.0);
+        let speed = ball.velocity.length();
+
+        assert!(arena.collide(0.0, &mut ball).is_some());
+        assert!(ball.velocity.y < 0.0);
+        assert!((ball.velocity.length() - speed).abs() < 0.01);
+        let angle = (ball.velocity.y / speed).asin().abs().to_degrees();
+        assert!(angle <= BALL_MAX_ANGLE + 0.01, "{}", angle);
+    }
+}
@@ -1,38 +1,537 @@
+use macroquad::audio::{load_sound, load_sound_from_bytes, play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound};
+use macroquad::prelude::Vec2;
+use serde::{Deserialize, Serialize};
+use crate::consts::*;
+use crate::events::{EventListener, GameEvent};
+use crate::music::{approach, Layer, MusicController, Song};
+use crate::storage;
+use crate::synth::{encode_wav, pan_stereo, render, transpose, Envelope, Tone, Waveform};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sfx {
+    Hit,
+    Wall,
+    Brick,
+    PowerUp,
+    Serve,
+    SpeedCap,
+    Score,
+    Win,
+    Select,
+}
+
+impl Sfx {
+    pub const ALL: [Sfx; 9] = [
+        Sfx::Hit,
+        Sfx::Wall,
+        Sfx::Brick,
+        Sfx::PowerUp,
+        Sfx::Serve,
+        Sfx::SpeedCap,
+        Sfx::Score,
+        Sfx::Win,
+        Sfx::Select,
+    ];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Sfx::Hit => "hit",
+            Sfx::Wall => "wall",
+            Sfx::Brick => "brick",
+            Sfx::PowerUp => "powerup",
+            Sfx::Serve => "serve",
+            Sfx::SpeedCap => "speed_cap",
+            Sfx::Score => "score",
+            Sfx::Win => "win",
+            Sfx::Select => "select",
+        }
+    }
+
+    pub fn positional(self) -> bool {
+        matches!(self, Sfx::Hit | Sfx::Wall | Sfx::Brick | Sfx::PowerUp | Sfx::SpeedCap)
+    }
+
+    pub fn pitch_steps(self) -> usize {
+        match self {
+            Sfx::Hit => HIT_PITCH_STEPS + HEIGHT_PITCH_STEPS - 1,
+            Sfx::Wall => WALL_PITCH_STEPS + HEIGHT_PITCH_STEPS - 1,
+            sfx if sfx.positional() => HEIGHT_PITCH_STEPS,
+            _ => 1,
+        }
+    }
+
+    pub fn pan_steps(self) -> usize {
+        if self.positional() { PAN_STEPS } else { 1 }
+    }
+
+    pub fn tones(self, pitch: usize) -> Vec<Tone> {
+        let step = pitch as f32;
+        let note = |waveform, frequency, start, length| Tone {
+            start,
+            envelope: Envelope::NOTE,
+            volume: 0.35,
+            ..Tone::new(waveform, frequency, length)
+        };
+        match self {
+            Sfx::Hit => vec![
+                Tone::new(Waveform::Square(0.5), transpose(440.0, step), 0.05),
+                Tone { volume: 0.25, ..Tone::new(Waveform::Triangle, transpose(220.0, step), 0.07) },
+            ],
+            Sfx::Wall => vec![Tone { slide: 0.85, ..Tone::new(Waveform::Triangle, transpose(330.0, step), 0.06) }],
+            Sfx::Brick => vec![
+                Tone { volume: 0.3, envelope: Envelope::CLICK, ..Tone::new(Waveform::Noise, 1000.0, 0.05) },
+                Tone { slide: 0.5, ..Tone::new(Waveform::Square(0.25), transpose(660.0, step), 0.08) },
+            ],
+            Sfx::PowerUp => [523.25, 659.25, 783.99, 1046.5]
+                .iter()
+                .enumerate()
+                .map(|(i, frequency)| note(Waveform::Square(0.25), transpose(*frequency, step), i as f32 * 0.05, 0.05))
+                .collect(),
+            Sfx::Serve => vec![Tone { slide: 1.5, volume: 0.4, ..Tone::new(Waveform::Sine, 660.0, 0.08) }],
+            Sfx::SpeedCap => vec![Tone { slide: 2.0, volume: 0.3, ..Tone::new(Waveform::Saw, transpose(330.0, step), 0.25) }],
+            Sfx::Score => vec![
+                note(Waveform::Square(0.25), 523.25, 0.0, 0.08),
+                note(Waveform::Square(0.25), 659.25, 0.09, 0.08),
+                note(Waveform::Square(0.25), 783.99, 0.18, 0.08),
+                note(Waveform::Square(0.5), 1046.5, 0.27, 0.2),
+            ],
+            Sfx::Win => [392.0, 523.25, 659.25, 783.99, 659.25, 783.99, 1046.5]
+                .iter()
+                .enumerate()
+                .map(|(i, frequency)| {
+                    let length = if i == 6 { 0.5 } else { 0.1 };
+                    note(Waveform::Square(0.5), *frequency, i as f32 * 0.12, length)
+                })
+                .chain([note(Waveform::Triangle, 261.63, 0.72, 0.5)])
+                .collect(),
+            Sfx::Select => vec![Tone { envelope: Envelope::CLICK, volume: 0.3, ..Tone::new(Waveform::Square(0.5), 1200.0, 0.015) }],
+        }
+    }
+
+    fn for_event(event: &GameEvent) -> Option<(Sfx, usize)> {
+        match *event {
+            GameEvent::PaddleHit { speed, offset, .. } => {
+                Some((Sfx::Hit, speed_pitch(speed) + (offset.abs() * 4.0).round() as usize))
+            }
+            GameEvent::BallCollision { .. } => Some((Sfx::Hit, 6)),
+            GameEvent::WallBounce { speed, .. } => Some((Sfx::Wall, speed_pitch(speed))),
+            GameEvent::ObstacleHit { .. } | GameEvent::ShieldBounce { .. } => Some((Sfx::Wall, 0)),
+            GameEvent::BrickHit { .. } => Some((Sfx::Brick, 0)),
+            GameEvent::PowerUpCollected { .. } => Some((Sfx::PowerUp, 0)),
+            GameEvent::Serve { .. } => Some((Sfx::Serve, 0)),
+            GameEvent::SpeedCapReached { .. } => Some((Sfx::SpeedCap, 0)),
+            GameEvent::Score { .. } | GameEvent::LifeLost { .. } => Some((Sfx::Score, 0)),
+            GameEvent::MatchEnd { .. } => Some((Sfx::Win, 0)),
+            _ => None,
+        }
+    }
+}
+
+fn speed_pitch(speed: f32) -> usize {
+    let t = (speed - BALL_INITIAL_SPEED) / (BALL_MAX_SPEED - BALL_INITIAL_SPEED);
+    (t.clamp(0.0, 1.0) * (WALL_PITCH_STEPS - 1) as f32).round() as usize
+}
+
+fn height_step(y: f32, steps: usize) -> usize {
+    ((1.0 - y / SCREEN_HEIGHT).clamp(0.0, 1.0) * (steps - 1) as f32).round() as usize
+}
+
+fn pan_step(x: f32) -> usize {
+    ((x / SCREEN_WIDTH).clamp(0.0, 1.0) * (PAN_STEPS - 1) as f32).round() as usize
+}
+
+fn encode_panned(samples: &[f32], pan: usize, pan_steps: usize) -> Vec<u8> {
+    if pan_steps == 1 {
+        return encode_wav(samples, 1);
+    }
+    encode_wav(&pan_stereo(samples, pan as f32 / (pan_steps - 1) as f32 * 2.0 - 1.0), 2)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Track {
+    Menu,
+    Game,
+}
+
+impl Track {
+    pub const ALL: [Track; 2] = [Track::Menu, Track::Game];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Track::Menu => "menu",
+            Track::Game => "game",
+        }
+    }
+
+    pub fn adaptive(self) -> bool {
+        self == Track::Game
+    }
+
+    pub fn song(self) -> Option<Song> {
+        let source = match self {
+            Track::Menu => include_str!("../data/music/menu.txt"),
+            Track::Game => include_str!("../data/music/game.txt"),
+        };
+        Song::parse(source)
+            .map_err(|err| eprintln!("Skipping {} music: {}", self.file_name(), err))
+            .ok()
+    }
+}
+
+struct Stem {
+    sound: Sound,
+    layer: Layer,
+    shifted: bool,
+    volume: f32,
+}
+
+struct Music {
+    track: Track,
+    stems: Vec<Stem>,
+    level: f32,
+    playing: bool,
+}
+
+fn sting_tones() -> Vec<Tone> {
+    let note = |waveform, frequency, start, length| Tone {
+        start,
+        envelope: Envelope::NOTE,
+        volume: 0.3,
+        ..Tone::new(waveform, frequency, length)
+    };
+    vec![
+        note(Waveform::Square(0.5), 880.0, 0.0, 0.1),
+        note(Waveform::Square(0.5), 659.25, 0.12, 0.1),
+        note(Waveform::Square(0.5), 523.25, 0.24, 0.1),
+        note(Waveform::Square(0.25), 587.33, 0.36, 0.45),
+        note(Waveform::Triangle, 146.83, 0.36, 0.45),
+        Tone { volume: 0.5, slide: 0.25, ..Tone::new(Waveform::Sine, 160.0, 0.1) },
+    ]
+}
+
+struct BallTone {
+    variants: Vec<Sound>,
+    voices: Vec<(usize, f32)>,
+}
+
+impl BallTone {
+    async fn load() -> Self {
+        let mut variants = Vec::new();
+        for pitch in 0..BALL_TONE_PITCH_STEPS {
+            let frequency = transpose(BALL_TONE_FREQUENCY, pitch as f32 * BALL_TONE_SEMITONES_PER_STEP);
+            let length = (frequency * BALL_TONE_LOOP_TIME).round() / frequency;
+            let samples = render(&[Tone {
+                envelope: Envelope::HOLD,
+                volume: 0.4,
+                ..Tone::new(Waveform::Triangle, frequency, length)
+            }]);
+            for pan in 0..PAN_STEPS {
+                match load_sound_from_bytes(&encode_panned(&samples, pan, PAN_STEPS)).await {
+                    Ok(sound) => variants.push(sound),
+                    Err(err) => eprintln!("Could not load ball tone: {}", err),
+                }
+            }
+        }
+        Self { variants, voices: Vec::new() }
+    }
+
+    fn update(&mut self, dt: f32, target: Option<usize>, volume: f32, enabled: bool) {
+        let target = target.filter(|index| *index < self.variants.len());
+        if let Some(index) = target {
+            if !self.voices.iter().any(|(voice, _)| *voice == index) {
+                if enabled {
+                    play_sound(&self.variants[index], PlaySoundParams { looped: true, volume: 0.0 });
+                }
+                self.voices.push((index, 0.0));
+            }
+        }
+        let step = dt / BALL_TONE_FADE_TIME;
+        for (index, level) in &mut self.voices {
+            *level = approach(*level, if target == Some(*index) { 1.0 } else { 0.0 }, step);
+            if enabled {
+                set_sound_volume(&self.variants[*index], *level * volume);
+            }
+        }
+        let variants = &self.variants;
+        self.voices.retain(|(index, level)| {
+            let silent = *level == 0.0 && target != Some(*index);
+            if silent && enabled {
+                stop_sound(&variants[*index]);
+            }
+            !silent
+        });
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub muted: bool,
+    pub ball_tone: bool,
+    pub tone_volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            sfx_volume: 0.8,
+            music_volume: 0.5,
+            muted: false,
+            ball_tone: false,
+            tone_volume: 0.4,
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn sfx(&self) -> f32 {
+        if self.muted { 0.0 } else { self.sfx_volume }
+    }
+
+    pub fn music(&self) -> f32 {
+        if self.muted { 0.0 } else { self.music_volume }
+    }
+
+    pub fn tone(&self) -> f32 {
+        if self.muted || !self.ball_tone { 0.0 } else { self.tone_volume }
+    }
+}
+
 pub struct AudioSystem {
-    menu_playing: bool,
-    game_playing: bool,
+    enabled: bool,
+    sounds: Vec<(Sfx, Vec<Vec<Sound>>)>,
+    music: Vec<Music>,
+    sting: Option<Sound>,
+    ball_tone: BallTone,
+    controller: MusicController,
+    current: Option<Track>,
+    settings: AudioSettings,
+}
+
+async fn load_first(dir: &str, name: &str) -> Option<Sound> {
+    for extension in AUDIO_EXTENSIONS {
+        if let Ok(sound) = load_sound(&format!("{}/{}.{}", dir, name, extension)).await {
+            return Some(sound);
+        }
+    }
+    None
+}
+
+async fn synthesize(sfx: Sfx) -> Vec<Vec<Sound>> {
+    let mut variants = Vec::new();
+    for pitch in 0..sfx.pitch_steps() {
+        let samples = render(&sfx.tones(pitch));
+        let mut panned = Vec::new();
+        for pan in 0..sfx.pan_steps() {
+            match load_sound_from_bytes(&encode_panned(&samples, pan, sfx.pan_steps())).await {
+                Ok(sound) => panned.push(sound),
+                Err(err) => eprintln!("Could not load {} sound: {}", sfx.file_name(), err),
+            }
+        }
+        if !panned.is_empty() {
+            variants.push(panned);
+        }
+    }
+    variants
+}
+
+async fn load_stem(samples: &[f32], layer: Layer, shifted: bool) -> Option<Stem> {
+    match load_sound_from_bytes(&encode_wav(samples, 1)).await {
+        Ok(sound) => Some(Stem { sound, layer, shifted, volume: 0.0 }),
+        Err(err) => {
+            eprintln!("Could not load music stem: {}", err);
+            None
+        }
+    }
+}
+
+async fn load_stems(track: Track) -> Vec<Stem> {
+    if let Some(sound) = load_first(MUSIC_DIR, track.file_name()).await {
+        return vec![Stem { sound, layer: Layer::Full, shifted: false, volume: 0.0 }];
+    }
+    let Some(song) = track.song() else {
+        return Vec::new();
+    };
+    let mut stems = Vec::new();
+    if !track.adaptive() {
+        stems.extend(load_stem(&song.render(), Layer::Full, false).await);
+        return stems;
+    }
+    let shifted = song.transposed(MUSIC_MATCH_POINT_SHIFT);
+    for (index, channel) in song.channels.iter().enumerate() {
+        let layer = Layer::for_channel(channel);
+        stems.extend(load_stem(&song.render_channel(index), layer, false).await);
+        if layer != Layer::Drums {
+            stems.extend(load_stem(&shifted.render_channel(index), layer, true).await);
+        }
+    }
+    stems
 }
 
 impl AudioSystem {
-    pub fn new() -> Self {
-        Self {
-            menu_playing: false,
-            game_playing: false,
+    pub async fn load() -> Self {
+        let mut system = Self {
+            enabled: cfg!(feature = "audio"),
+            sounds: Vec::new(),
+            music: Vec::new(),
+            sting: None,
+            ball_tone: BallTone { variants: Vec::new(), voices: Vec::new() },
+            controller: MusicController::new(),
+            current: None,
+            settings: AudioSettings::default(),
+        };
+        if !system.enabled {
+            return system;
+        }
+
+        for sfx in Sfx::ALL {
+            let variants = match load_first(SOUND_DIR, sfx.file_name()).await {
+                Some(sound) => vec![vec![sound]],
+                None => synthesize(sfx).await,
+            };
+            system.sounds.push((sfx, variants));
+        }
+        for track in Track::ALL {
+            let stems = load_stems(track).await;
+            if !stems.is_empty() {
+                system.music.push(Music { track, stems, level: 0.0, playing: false });
+            }
         }
+        system.sting = match load_first(MUSIC_DIR, "sting").await {
+            Some(sound) => Some(sound),
+            None => load_sound_from_bytes(&encode_wav(&render(&sting_tones()), 1)).await.ok(),
+        };
+        system.ball_tone = BallTone::load().await;
+        system
+    }
+
+    pub fn apply_settings(&mut self, settings: AudioSettings) {
+        self.settings = settings;
+    }
+
+    fn crossfade(&mut self, track: Track, dt: f32) {
+        if self.current != Some(track) {
+            self.current = Some(track);
+            self.controller = MusicController::new();
+        }
+        self.controller.update(dt);
+        let step = dt / MUSIC_CROSSFADE_TIME;
+        let volume = self.settings.music();
+        for music in &mut self.music {
+            let target = if music.track == track { 1.0 } else { 0.0 };
+            music.level = approach(music.level, target, step);
+            if !self.enabled {
+                continue;
+            }
+            if music.level > 0.0 && !music.playing {
+                music.playing = true;
+                for stem in &mut music.stems {
+                    stem.volume = 0.0;
+                    play_sound(&stem.sound, PlaySoundParams { looped: true, volume: 0.0 });
+                }
+            } else if music.level == 0.0 && music.playing {
+                music.playing = false;
+                for stem in &music.stems {
+                    stop_sound(&stem.sound);
+                }
+                continue;
+            }
+            for stem in &mut music.stems {
+                let stem_volume = music.level * volume * self.controller.stem_level(stem.layer, stem.shifted);
+                if (stem_volume - stem.volume).abs() > 0.001 {
+                    stem.volume = stem_volume;
+                    set_sound_volume(&stem.sound, stem_volume);
+                }
+            }
+        }
+    }
+
+    pub fn update_menu(&mut self, dt: f32) {
+        self.crossfade(Track::Menu, dt);
     }
 
-    pub fn update_menu(&mut self, _dt: f32) {
-        self.menu_playing = true;
+    pub fn update_game(&mut self, dt: f32) {
+        self.crossfade(Track::Game, dt);
     }
 
-    pub fn update_game(&mut self, _dt: f32) {
-        if !self.game_playing {
-            self.game_playing = true;
-            self.menu_playing = false;
+    pub fn play(&mut self, sfx: Sfx, pitch: usize, position: Option<Vec2>) {
+        let volume = self.settings.sfx();
+        if !self.enabled || volume <= 0.0 {
+            return;
+        }
+        let Some((_, variants)) = self.sounds.iter().find(|(candidate, _)| *candidate == sfx) else {
+            return;
+        };
+        let (height, pan) = match position.filter(|_| sfx.positional()) {
+            Some(position) => (height_step(position.y, HEIGHT_PITCH_STEPS), pan_step(position.x)),
+            None => (HEIGHT_PITCH_STEPS / 2, PAN_STEPS / 2),
+        };
+        let Some(panned) = variants.get((pitch + height).min(variants.len().saturating_sub(1))) else {
+            return;
+        };
+        if let Some(sound) = panned.get(pan.min(panned.len().saturating_sub(1))) {
+            play_sound(sound, PlaySoundParams { looped: false, volume });
         }
     }
 
-    pub fn play_hit_sound(&mut self) {
+    pub fn update_ball_tone(&mut self, dt: f32, ball: Option<Vec2>) {
+        let volume = self.settings.tone();
+        let target = ball
+            .filter(|_| volume > 0.0)
+            .map(|ball| height_step(ball.y, BALL_TONE_PITCH_STEPS) * PAN_STEPS + pan_step(ball.x));
+        self.ball_tone.update(dt, target, volume, self.enabled);
     }
 
-    pub fn play_select_sound(&mut self) {
+    fn play_sting(&mut self) {
+        let volume = self.settings.music();
+        if !self.enabled || volume <= 0.0 {
+            return;
+        }
+        if let Some(sting) = &self.sting {
+            play_sound(sting, PlaySoundParams { looped: false, volume });
+        }
+    }
+
+    pub fn play_events(&mut self, events: &[GameEvent]) {
+        for event in events {
+            self.on_event(event);
+        }
     }
+}
 
-    pub fn play_score_sound(&mut self) {
+pub fn render_music() {
+    for track in Track::ALL {
+        let Some(song) = track.song() else {
+            continue;
+        };
+        let file_name = format!("music-{}.wav", track.file_name());
+        match storage::save_bytes(&file_name, &encode_wav(&song.render(), 1)) {
+            Ok(path) => println!("Rendered \"{}\" ({:.1}s) to {}", song.title, song.duration(), path.display()),
+            Err(err) => eprintln!("Could not write {}: {}", file_name, err),
+        }
+        if !track.adaptive() {
+            continue;
+        }
+        for (index, channel) in song.channels.iter().enumerate() {
+            let file_name = format!("music-{}-{}.wav", track.file_name(), channel.name);
+            match storage::save_bytes(&file_name, &encode_wav(&song.render_channel(index), 1)) {
+                Ok(path) => println!("  {} stem to {}", channel.name, path.display()),
+                Err(err) => eprintln!("Could not write {}: {}", file_name, err),
+            }
+        }
     }
+}
 
-    pub fn stop(&mut self) {
-        self.menu_playing = false;
-        self.game_playing = false;
+impl EventListener for AudioSystem {
+    fn on_event(&mut self, event: &GameEvent) {
+        if let Some((sfx, pitch)) = Sfx::for_event(event) {
+            self.play(sfx, pitch, event.position());
+        }
+        if let GameEvent::Score { .. } = event {
+            self.play_sting();
+        }
+        self.controller.on_event(event);
     }
 }
@@ -2,6 +2,7 @@ use macroquad::prelude::*;
 use crate::consts::*;
 use crate::effects::*;
 use crate::paddle::Paddle;
+use crate::rules::Side;
 
 pub struct Ball {
     pub position: Vec2,
@@ -9,6 +10,14 @@ pub struct Ball {
     pub speed: f32,
     pub hue: f32,
     pub trail: Trail,
+    pub spin: f32,
+    pub spin_enabled: bool,
+    pub last_touched: Option<Side>,
+    pub any_angle: bool,
+    pub speed_cap: f32,
+    pub wall_bounce: Option<Side>,
+    previous_position: Vec2,
+    rotation: f32,
 }
 
 impl Ball {
@@ -19,89 +28,190 @@ impl Ball {
             speed: BALL_INITIAL_SPEED,
             hue: 120.0,
             trail: Trail::new(),
+            spin: 0.0,
+            spin_enabled: false,
+            last_touched: None,
+            any_angle: false,
+            speed_cap: BALL_MAX_SPEED,
+            wall_bounce: None,
+            previous_position: Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0),
+            rotation: 0.0,
         }
     }
 
     pub fn reset(&mut self) {
         self.position = Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0);
-        let angle = (macroquad::rand::gen_range(-45, 45) as f32).to_radians();
-        let direction = if macroquad::rand::gen_range(0, 2) == 0 { -1.0 } else { 1.0 };
-        self.velocity = Vec2::new(
-            direction * angle.cos() * self.speed,
-            angle.sin() * self.speed,
-        );
+        self.velocity = Vec2::ZERO;
+        self.spin = 0.0;
+        self.last_touched = None;
         self.hue = (self.hue + 60.0) % 360.0;
     }
 
-    pub fn update(&mut self, dt: f32, left_paddle: &Paddle, right_paddle: &Paddle) -> Option<CollisionResult> {
+    pub fn split(&self, angle: f32) -> Ball {
+        let mut ball = Ball::new();
+        ball.position = self.position;
+        ball.previous_position = self.position;
+        ball.any_angle = self.any_angle;
+        ball.velocity = Vec2::from_angle(angle.to_radians()).rotate(self.velocity);
+        ball.limit_angle();
+        ball.speed = self.speed;
+        ball.hue = (self.hue + 120.0 + angle) % 360.0;
+        ball.spin_enabled = self.spin_enabled;
+        ball.last_touched = self.last_touched;
+        ball
+    }
+
+    pub fn hold(&mut self, dt: f32, position: Vec2) {
+        self.position = position;
+        self.previous_position = position;
+        self.velocity = Vec2::ZERO;
+        self.trail.update(dt);
+        self.trail.add_point(self.position, self.hue);
+    }
+
+    pub fn launch(&mut self, direction: Vec2, angle: f32) {
+        self.velocity = Vec2::from_angle(angle).rotate(direction) * self.speed;
+    }
+
+    pub fn update(&mut self, dt: f32, paddles: &[Paddle], walls: [bool; 4]) -> Option<CollisionResult> {
+        self.previous_position = self.position;
+        self.wall_bounce = None;
+        self.apply_spin(dt);
         self.position += self.velocity * dt;
 
-        let collision = self.check_paddle_collision(left_paddle, right_paddle);
-        
-        if self.position.y <= BALL_SIZE / 2.0 || self.position.y >= SCREEN_HEIGHT - BALL_SIZE / 2.0 {
-            self.velocity.y = -self.velocity.y;
-            self.position.y = self.position.y
-                .max(BALL_SIZE / 2.0)
-                .min(SCREEN_HEIGHT - BALL_SIZE / 2.0);
+        let collision = self.check_paddle_collision(paddles);
+
+        let radius = BALL_SIZE / 2.0;
+        for side in Side::ALL {
+            if !walls[side.index()] {
+                continue;
+            }
+            let normal = side.normal();
+            let distance = match side {
+                Side::Left => self.position.x,
+                Side::Right => SCREEN_WIDTH - self.position.x,
+                Side::Top => self.position.y,
+                Side::Bottom => SCREEN_HEIGHT - self.position.y,
+            } - radius;
+            if distance > 0.0 {
+                continue;
+            }
+            self.position -= normal * distance;
+            let approach = self.velocity.dot(normal);
+            if approach < 0.0 {
+                self.velocity -= normal * approach * 2.0;
+                self.spin_wall_kick();
+                self.wall_bounce = Some(side);
+            }
         }
 
         self.trail.update(dt);
         self.trail.add_point(self.position, self.hue);
 
-        if let Some(coll) = collision {
-            Some(coll)
-        } else if self.position.x < 0.0 || self.position.x > SCREEN_WIDTH {
-            None
-        } else {
-            None
+        collision
+    }
+
+    fn apply_spin(&mut self, dt: f32) {
+        self.rotation += self.spin * dt * 4.0;
+        if self.spin == 0.0 {
+            return;
+        }
+
+        self.velocity = Vec2::from_angle(self.spin * SPIN_CURVE * dt).rotate(self.velocity);
+        self.limit_angle();
+
+        self.spin *= 1.0 - SPIN_DECAY * dt;
+        if self.spin.abs() < 0.05 {
+            self.spin = 0.0;
         }
     }
 
-    fn check_paddle_collision(&mut self, left_paddle: &Paddle, right_paddle: &Paddle) -> Option<CollisionResult> {
-        let ball_rect = self.get_rect();
-        
-        let left_rect = left_paddle.get_rect();
-        let right_rect = right_paddle.get_rect();
+    fn spin_wall_kick(&mut self) {
+        if self.spin == 0.0 {
+            return;
+        }
 
-        if ball_rect.overlaps(&left_rect) && self.velocity.x < 0.0 {
-            self.handle_paddle_hit(left_paddle);
-            return Some(CollisionResult {
-                position: self.position,
-                hue: self.hue,
-            });
+        let kick = self.spin * SPIN_WALL_KICK * self.velocity.x.signum() * self.velocity.y.signum();
+        self.velocity = Vec2::from_angle(kick).rotate(self.velocity);
+        self.limit_angle();
+        self.spin *= -0.5;
+    }
+
+    pub fn limit_angle(&mut self) {
+        let speed = self.velocity.length();
+        if speed == 0.0 || self.any_angle {
+            return;
+        }
+        let max_angle = BALL_MAX_ANGLE.to_radians();
+        let angle = (self.velocity.y / speed).asin();
+        if angle.abs() > max_angle {
+            let angle = angle.clamp(-max_angle, max_angle);
+            self.velocity = Vec2::new(self.velocity.x.signum() * angle.cos(), angle.sin()) * speed;
         }
+    }
+
+    fn check_paddle_collision(&mut self, paddles: &[Paddle]) -> Option<CollisionResult> {
+        let ball_rect = self.get_rect();
 
-        if ball_rect.overlaps(&right_rect) && self.velocity.x > 0.0 {
-            self.handle_paddle_hit(right_paddle);
-            return Some(CollisionResult {
-                position: self.position,
-                hue: self.hue,
-            });
+        for paddle in paddles {
+            if ball_rect.overlaps(&paddle.get_rect()) && self.velocity.dot(paddle.side.normal()) < 0.0 {
+                self.handle_paddle_hit(paddle);
+                self.last_touched = Some(paddle.side);
+                return Some(CollisionResult {
+                    position: self.position,
+                    hue: self.hue,
+                });
+            }
         }
 
         None
     }
 
     fn handle_paddle_hit(&mut self, paddle: &Paddle) {
+        let normal = paddle.side.normal();
+        let axis = paddle.axis();
         let paddle_center = paddle.get_center();
-        let relative_y = (self.position.y - paddle_center.y) / (PADDLE_HEIGHT / 2.0);
-        let bounce_angle = relative_y * 60.0_f32.to_radians();
-        
-        self.velocity = Vec2::new(
-            -self.velocity.x.signum() * bounce_angle.cos() * self.speed,
-            bounce_angle.sin() * self.speed,
-        );
+        let relative = (self.position - paddle_center).dot(axis) / (paddle.height() / 2.0);
+        let bounce_angle = relative * 60.0_f32.to_radians();
 
-        self.position.x = if self.position.x < SCREEN_WIDTH / 2.0 {
-            PADDLE_MARGIN + PADDLE_WIDTH + BALL_SIZE / 2.0
+        let push = paddle.velocity.dot(normal);
+        let return_speed = if push > 0.0 {
+            self.speed + push * PADDLE_SMASH_TRANSFER
         } else {
-            SCREEN_WIDTH - PADDLE_MARGIN - PADDLE_WIDTH - BALL_SIZE / 2.0
+            (self.speed + push * PADDLE_SMASH_TRANSFER).max(BALL_INITIAL_SPEED)
         };
+        self.velocity = (normal * bounce_angle.cos() + axis * bounce_angle.sin()) * return_speed.min(self.speed_cap);
 
-        self.speed = (self.speed + BALL_SPEED_INCREASE).min(BALL_MAX_SPEED);
+        let depth = (self.position - paddle_center).dot(normal);
+        self.position += normal * (PADDLE_WIDTH / 2.0 + BALL_SIZE / 2.0 - depth);
+
+        if self.spin_enabled {
+            self.spin = (self.spin + paddle.velocity.dot(normal.perp()) * SPIN_TRANSFER)
+                .clamp(-SPIN_MAX, SPIN_MAX);
+        }
+
+        self.speed = (self.speed + BALL_SPEED_INCREASE).min(self.speed_cap);
         self.hue = (self.hue + 30.0) % 360.0;
     }
 
+    pub fn bounce_off_shield(&mut self, side: Side) -> bool {
+        let inset = SHIELD_INSET + 3.0 + BALL_SIZE / 2.0;
+        let distance = match side {
+            Side::Left => self.position.x,
+            Side::Right => SCREEN_WIDTH - self.position.x,
+            Side::Top => self.position.y,
+            Side::Bottom => SCREEN_HEIGHT - self.position.y,
+        };
+        let normal = side.normal();
+        let approach = self.velocity.dot(normal);
+        if !(approach < 0.0 && distance <= inset) {
+            return false;
+        }
+        self.velocity -= normal * approach * 2.0;
+        self.position += normal * (inset - distance);
+        true
+    }
+
     pub fn get_rect(&self) -> Rect {
         Rect::new(
             self.position.x - BALL_SIZE / 2.0,
@@ -112,6 +222,9 @@ impl Ball {
     }
 
     pub fn draw(&self, phase: f32) {
+        if self.spin_enabled {
+            self.trail.draw_ribbon(phase);
+        }
         self.trail.draw(phase);
 
         let color = get_rainbow_color((self.hue + phase) % 360.0);
@@ -127,13 +240,33 @@ impl Ball {
             BALL_SIZE / 3.0,
             inner_color,
         );
+
+        if self.spin_enabled {
+            let stripe_color = get_rainbow_color((self.hue + phase + 180.0) % 360.0);
+            for i in 0..2 {
+                let angle = self.rotation + i as f32 * std::f32::consts::FRAC_PI_2;
+                let offset = Vec2::from_angle(angle) * (BALL_SIZE / 2.0 - 1.0);
+                draw_line(
+                    self.position.x - offset.x,
+                    self.position.y - offset.y,
+                    self.position.x + offset.x,
+                    self.position.y + offset.y,
+                    2.0,
+                    stripe_color,
+                );
+            }
+        }
     }
 
-    pub fn scored(&self) -> Option<bool> {
+    pub fn scored(&self) -> Option<Side> {
         if self.position.x < 0.0 {
-            Some(false)
+            Some(Side::Left)
         } else if self.position.x > SCREEN_WIDTH {
-            Some(true)
+            Some(Side::Right)
+        } else if self.position.y < 0.0 {
+            Some(Side::Top)
+        } else if self.position.y > SCREEN_HEIGHT {
+            Some(Side::Bottom)
         } else {
             None
         }
@@ -145,3 +278,136 @@ pub struct CollisionResult {
     pub hue: f32,
 }
 
+
+pub fn resolve_ball_collisions(balls: &mut [Ball], dt: f32) -> Vec<CollisionResult> {
+    let mut contacts = Vec::new();
+    for i in 0..balls.len() {
+        for j in (i + 1)..balls.len() {
+            if let Some(time) = time_of_impact(&balls[i], &balls[j]) {
+                contacts.push((time, i, j));
+            }
+        }
+    }
+    contacts.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    let mut resolved = vec![false; balls.len()];
+    let mut collisions = Vec::new();
+    for (time, i, j) in contacts {
+        if resolved[i] || resolved[j] {
+            continue;
+        }
+        resolved[i] = true;
+        resolved[j] = true;
+
+        let contact_i = balls[i].previous_position.lerp(balls[i].position, time);
+        let contact_j = balls[j].previous_position.lerp(balls[j].position, time);
+        let normal = (contact_i - contact_j).try_normalize().unwrap_or(Vec2::X);
+        let approach = (balls[i].velocity - balls[j].velocity).dot(normal);
+        if approach >= 0.0 {
+            continue;
+        }
+
+        balls[i].velocity -= normal * approach;
+        balls[j].velocity += normal * approach;
+
+        let remaining = (1.0 - time) * dt;
+        for (index, contact) in [(i, contact_i), (j, contact_j)] {
+            let ball = &mut balls[index];
+            ball.limit_angle();
+            ball.speed = ball.velocity.length().clamp(BALL_INITIAL_SPEED, ball.speed_cap);
+            ball.velocity = ball.velocity.normalize_or_zero() * ball.speed;
+            ball.position = contact + ball.velocity * remaining;
+            ball.hue = (ball.hue + 45.0) % 360.0;
+        }
+
+        collisions.push(CollisionResult {
+            position: (contact_i + contact_j) / 2.0,
+            hue: (balls[i].hue + balls[j].hue) / 2.0,
+        });
+    }
+
+    collisions
+}
+
+fn time_of_impact(a: &Ball, b: &Ball) -> Option<f32> {
+    let start = a.previous_position - b.previous_position;
+    let motion = (a.position - a.previous_position) - (b.position - b.previous_position);
+    let radius = BALL_SIZE;
+
+    let a_coef = motion.length_squared();
+    let b_coef = 2.0 * start.dot(motion);
+    if a_coef == 0.0 || b_coef >= 0.0 {
+        return None;
+    }
+
+    let c = start.length_squared() - radius * radius;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+
+    let discriminant = b_coef * b_coef - 4.0 * a_coef * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let time = (-b_coef - discriminant.sqrt()) / (2.0 * a_coef);
+    (0.0..=1.0).contains(&time).then_some(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_ball_leaving_the_court_is_conceded_by_the_side_it_passed() {
+        let mut ball = Ball::new();
+        assert_eq!(ball.scored(), None);
+
+        ball.position.x = -1.0;
+        assert_eq!(ball.scored(), Some(Side::Left));
+        assert_eq!(ball.scored().map(Side::opponent), Some(Side::Right));
+
+        ball.position.x = SCREEN_WIDTH + 1.0;
+        assert_eq!(ball.scored(), Some(Side::Right));
+        assert_eq!(ball.scored().map(Side::opponent), Some(Side::Left));
+    }
+
+    fn moving_ball(from: Vec2, velocity: Vec2, dt: f32) -> Ball {
+        let mut ball = Ball::new();
+        ball.previous_position = from;
+        ball.position = from + velocity * dt;
+        ball.velocity = velocity;
+        ball.speed = velocity.length();
+        ball
+    }
+
+    #[test]
+    fn the_earliest_contact_is_resolved_first() {
+        let dt = 0.1;
+        let late = moving_ball(Vec2::new(200.0 + BALL_SIZE + 40.0, 300.0), Vec2::new(-500.0, 0.0), dt);
+        let middle = moving_ball(Vec2::new(200.0, 300.0), Vec2::ZERO, dt);
+        let early = moving_ball(Vec2::new(200.0 - BALL_SIZE - 10.0, 300.0), Vec2::new(500.0, 0.0), dt);
+        let mut balls = [late, middle, early];
+
+        let collisions = resolve_ball_collisions(&mut balls, dt);
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(balls[0].velocity, Vec2::new(-500.0, 0.0));
+        assert!(balls[1].velocity.x > 0.0);
+        assert!(balls[2].velocity.x < 500.0);
+    }
+
+    #[test]
+    fn collisions_clamp_each_ball_to_its_own_speed_cap() {
+        let dt = 0.1;
+        let mut capped = moving_ball(Vec2::new(300.0, 300.0), Vec2::new(700.0, 0.0), dt);
+        capped.speed_cap = 500.0;
+        let free = moving_ball(Vec2::new(400.0 + BALL_SIZE, 300.0), Vec2::new(-700.0, 0.0), dt);
+        let mut balls = [capped, free];
+
+        assert_eq!(resolve_ball_collisions(&mut balls, dt).len(), 1);
+        assert_eq!(balls[0].speed, 500.0);
+        assert!((balls[0].velocity - Vec2::new(-500.0, 0.0)).length() < 0.01);
+        assert_eq!(balls[1].speed, BALL_MAX_SPEED);
+        assert!((balls[1].velocity - Vec2::new(BALL_MAX_SPEED, 0.0)).length() < 0.01);
+    }
+}
@@ -0,0 +1,107 @@
+use macroquad::prelude::*;
+use crate::consts::*;
+use crate::effects::*;
+use crate::ball::Ball;
+
+pub struct Brick {
+    pub rect: Rect,
+    pub hp: u32,
+    pub max_hp: u32,
+    pub hue: f32,
+}
+
+pub struct BrickHit {
+    pub position: Vec2,
+    pub hue: f32,
+    pub destroyed: bool,
+}
+
+pub struct BrickWall {
+    pub bricks: Vec<Brick>,
+}
+
+impl BrickWall {
+    pub fn new() -> Self {
+        let mut wall = Self { bricks: Vec::new() };
+        wall.build();
+        wall
+    }
+
+    fn build(&mut self) {
+        let total_height = BRICK_ROWS as f32 * (BRICK_HEIGHT + BRICK_GAP) - BRICK_GAP;
+        let total_width = BRICK_COLUMNS as f32 * (BRICK_WIDTH + BRICK_GAP) - BRICK_GAP;
+        let start = Vec2::new(SCREEN_WIDTH - total_width, SCREEN_HEIGHT - total_height) / 2.0;
+
+        self.bricks.clear();
+        for column in 0..BRICK_COLUMNS {
+            for row in 0..BRICK_ROWS {
+                let hp = if column == BRICK_COLUMNS / 2 { 3 } else if row % 2 == 0 { 2 } else { 1 };
+                self.bricks.push(Brick {
+                    rect: Rect::new(
+                        start.x + column as f32 * (BRICK_WIDTH + BRICK_GAP),
+                        start.y + row as f32 * (BRICK_HEIGHT + BRICK_GAP),
+                        BRICK_WIDTH,
+                        BRICK_HEIGHT,
+                    ),
+                    hp,
+                    max_hp: hp,
+                    hue: (row as f32 * 360.0 / BRICK_ROWS as f32 + column as f32 * 20.0) % 360.0,
+                });
+            }
+        }
+    }
+
+    pub fn collide(&mut self, ball: &mut Ball) -> Option<BrickHit> {
+        let radius = BALL_SIZE / 2.0;
+        let index = self.bricks.iter().position(|brick| {
+            let closest = ball.position.clamp(brick.rect.point(), brick.rect.point() + brick.rect.size());
+            closest.distance(ball.position) < radius
+        })?;
+
+        let brick = &mut self.bricks[index];
+        let center = brick.rect.center();
+        let offset = ball.position - center;
+        let overlap_x = brick.rect.w / 2.0 + radius - offset.x.abs();
+        let overlap_y = brick.rect.h / 2.0 + radius - offset.y.abs();
+        if overlap_x < overlap_y {
+            ball.position.x += overlap_x * offset.x.signum();
+            if ball.velocity.x * offset.x < 0.0 {
+                ball.velocity.x = -ball.velocity.x;
+            }
+        } else {
+            ball.position.y += overlap_y * offset.y.signum();
+            if ball.velocity.y * offset.y < 0.0 {
+                ball.velocity.y = -ball.velocity.y;
+            }
+        }
+
+        brick.hp -= 1;
+        let hit = BrickHit {
+            position: center,
+            hue: brick.hue,
+            destroyed: brick.hp == 0,
+        };
+        if hit.destroyed {
+            self.bricks.remove(index);
+            if self.bricks.is_empty() {
+                self.build();
+            }
+        }
+        Some(hit)
+    }
+
+    pub fn draw(&self, phase: f32) {
+        for brick in &self.bricks {
+            let color = get_rainbow_color((brick.hue + phase) % 360.0);
+            let strength = brick.hp as f32 / brick.max_hp as f32;
+            let fill = Color::new(color.r * strength, color.g * strength, color.b * strength, 0.5 + strength * 0.4);
+            let rect = brick.rect;
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, fill);
+            draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, color);
+            for crack in 0..(brick.max_hp - brick.hp) {
+                let y = rect.y + rect.h * (crack as f32 + 1.0) / (brick.max_hp as f32 + 1.0);
+                draw_line(rect.x + 2.0, y - 4.0, rect.x + rect.w - 2.0, y + 4.0, 1.5, Color::new(0.0, 0.0, 0.0, 0.7));
+            }
+        }
+    }
+}
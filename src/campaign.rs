@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use crate::consts::*;
+use crate::ai::{AiStyle, Difficulty, Personality};
+use crate::powerups::{PowerUpKind, PowerUpSet};
+use crate::rules::{GameMode, MatchRules};
+use crate::storage;
+
+pub struct Opponent {
+    pub name: &'static str,
+    pub taunt: &'static str,
+    pub arena: &'static str,
+    pub rules: MatchRules,
+}
+
+impl Opponent {
+    fn new(name: &'static str, taunt: &'static str, arena: &'static str, difficulty: Difficulty, personality: Personality) -> Self {
+        let mut rules = MatchRules::classic();
+        rules.ai = AiStyle { difficulty, personality };
+        Self { name, taunt, arena, rules }
+    }
+
+    fn with(mut self, change: impl FnOnce(&mut MatchRules)) -> Self {
+        change(&mut self.rules);
+        self
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            self.name.to_string(),
+            self.rules.ai.label(),
+            format!("ARENA: {}", self.arena.to_uppercase()),
+            format!("MODE: {}", self.rules.mode.label()),
+            self.rules.short_label(),
+        ];
+        if self.rules.spin {
+            lines.push("BALL SPIN ON".to_string());
+        }
+        if self.rules.paddle_zone > 0.0 {
+            lines.push(format!("PADDLES STEP UP {}", self.rules.paddle_zone));
+        }
+        if self.rules.power_ups.count() > 0 {
+            lines.push(format!("{} POWER-UPS ON", self.rules.power_ups.count()));
+        }
+        lines
+    }
+}
+
+pub fn ladder() -> Vec<Opponent> {
+    vec![
+        Opponent::new("ROOKIE RAY", "First day with a paddle.", "Classic", Difficulty::Easy, Personality::Steady)
+            .with(|rules| rules.target_score = 5),
+        Opponent::new("BRICKLAYER BEA", "Mind the wall.", "Classic", Difficulty::Easy, Personality::Defensive)
+            .with(|rules| rules.mode = GameMode::Breakout),
+        Opponent::new("SPINNING SAL", "Curves all day.", "Pillars", Difficulty::Normal, Personality::Trickster)
+            .with(|rules| rules.spin = true),
+        Opponent::new("DOUBLE DEX", "Why hit one ball?", "Classic", Difficulty::Normal, Personality::Steady)
+            .with(|rules| rules.mode = GameMode::MultiBall),
+        Opponent::new("PRESSING PIA", "Always at the net.", "Windmill", Difficulty::Normal, Personality::Aggressive)
+            .with(|rules| rules.paddle_zone = PADDLE_ZONE_DEPTHS[2]),
+        Opponent::new("CAPTAIN CHAOS", "Anything can happen.", "Pillars", Difficulty::Hard, Personality::Trickster)
+            .with(|rules| {
+                rules.power_ups = PowerUpSet::all();
+                rules.power_ups.toggle(PowerUpKind::ReverseControls);
+            }),
+        Opponent::new("THE WARDEN", "Nothing gets past.", "Fortress", Difficulty::Hard, Personality::Defensive)
+            .with(|rules| {
+                rules.target_score = 11;
+                rules.win_by_two = true;
+                rules.serve_every = 2;
+            }),
+        Opponent::new("GRANDMASTER GLOW", "The final rally.", "Windmill", Difficulty::Expert, Personality::Aggressive)
+            .with(|rules| {
+                rules.target_score = 11;
+                rules.win_by_two = true;
+                rules.spin = true;
+                rules.paddle_zone = PADDLE_ZONE_DEPTHS[1];
+            }),
+    ]
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct CampaignProgress {
+    #[serde(default)]
+    pub cleared: usize,
+}
+
+impl CampaignProgress {
+    pub fn load() -> Self {
+        storage::load(CAMPAIGN_FILE)
+    }
+
+    pub fn is_unlocked(&self, stage: usize) -> bool {
+        stage <= self.cleared
+    }
+
+    pub fn record_win(&mut self, stage: usize) {
+        if self.advance(stage) {
+            if let Err(err) = storage::save(CAMPAIGN_FILE, self) {
+                eprintln!("Could not save campaign progress: {}", err);
+            }
+        }
+    }
+
+    fn advance(&mut self, stage: usize) -> bool {
+        if stage < self.cleared {
+            return false;
+        }
+        self.cleared = stage + 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::Arena;
+
+    #[test]
+    fn beating_the_newest_opponent_unlocks_the_next() {
+        let mut progress = CampaignProgress::default();
+        assert!(progress.is_unlocked(0));
+        assert!(!progress.is_unlocked(1));
+
+        assert!(progress.advance(0));
+        assert!(progress.is_unlocked(1));
+        assert!(progress.advance(1));
+        assert_eq!(progress.cleared, 2);
+
+        assert!(!progress.advance(0));
+        assert_eq!(progress.cleared, 2);
+    }
+
+    #[test]
+    fn every_opponent_plays_in_a_built_in_arena() {
+        for opponent in ladder() {
+            assert!(Arena::is_builtin(opponent.arena), "{} plays in {}", opponent.name, opponent.arena);
+        }
+    }
+}
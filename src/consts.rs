@@ -5,14 +5,88 @@ pub const PADDLE_WIDTH: f32 = 15.0;
 pub const PADDLE_HEIGHT: f32 = 100.0;
 pub const PADDLE_SPEED: f32 = 400.0;
 pub const PADDLE_MARGIN: f32 = 30.0;
+pub const DOUBLES_FORWARD_OFFSET: f32 = 180.0;
+pub const PADDLE_ZONE_DEPTHS: [f32; 4] = [0.0, 60.0, 120.0, 200.0];
+pub const PADDLE_SMASH_TRANSFER: f32 = 0.3;
 
 pub const BALL_SIZE: f32 = 15.0;
 pub const BALL_INITIAL_SPEED: f32 = 300.0;
 pub const BALL_SPEED_INCREASE: f32 = 20.0;
 pub const BALL_MAX_SPEED: f32 = 600.0;
+pub const BALL_MAX_ANGLE: f32 = 60.0;
+pub const MULTI_BALL_COUNT: usize = 3;
+pub const MULTI_BALL_SPREAD: f32 = 20.0;
+
+pub const SPIN_TRANSFER: f32 = 0.008;
+pub const SPIN_MAX: f32 = 3.0;
+pub const SPIN_CURVE: f32 = 0.3;
+pub const SPIN_DECAY: f32 = 0.6;
+pub const SPIN_WALL_KICK: f32 = 0.12;
 
 pub const WIN_SCORE: u32 = 7;
+pub const MAX_WIN_SCORE: u32 = 21;
+pub const FOUR_PLAYER_LIVES: u32 = 3;
+pub const MAX_LIVES: u32 = 9;
+
+pub const SERVE_COUNTDOWN: f32 = 3.0;
+pub const SERVE_MAX_ANGLE: f32 = 45.0;
+pub const SERVE_AIM_SPEED: f32 = 90.0;
+
+pub const POWERUP_SPAWN_INTERVAL: f32 = 7.0;
+pub const POWERUP_LIFETIME: f32 = 10.0;
+pub const POWERUP_MAX_PICKUPS: usize = 2;
+pub const POWERUP_RADIUS: f32 = 18.0;
+pub const SHIELD_INSET: f32 = 8.0;
+
+pub const BRICK_COLUMNS: usize = 3;
+pub const BRICK_ROWS: usize = 8;
+pub const BRICK_WIDTH: f32 = 22.0;
+pub const BRICK_HEIGHT: f32 = 56.0;
+pub const BRICK_GAP: f32 = 8.0;
+
+pub const PRACTICE_SPEEDS: [f32; 4] = [220.0, 300.0, 400.0, 500.0];
+pub const PRACTICE_INTERVALS: [f32; 4] = [2.5, 1.5, 1.0, 0.6];
+pub const PRACTICE_SHOT_COUNTS: [u32; 3] = [10, 20, 40];
+pub const PRACTICE_TARGET_SIZE: f32 = 160.0;
+
+pub const SURVIVAL_ACCELERATION: f32 = 8.0;
+pub const HIGH_SCORE_COUNT: usize = 10;
+pub const ACHIEVEMENT_TOAST_TIME: f32 = 3.5;
+
+pub const SAVE_DIR: &str = "save";
+pub const HIGH_SCORE_FILE: &str = "highscores.toml";
+pub const CAMPAIGN_FILE: &str = "campaign.toml";
+pub const PROFILE_FILE: &str = "profiles.toml";
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+pub const SOUND_DIR: &str = "assets/sounds";
+pub const MUSIC_DIR: &str = "assets/music";
+pub const AUDIO_EXTENSIONS: [&str; 2] = ["wav", "ogg"];
+pub const VOLUME_STEP: f32 = 0.1;
+pub const SYNTH_SAMPLE_RATE: u32 = 44100;
+pub const PAN_STEPS: usize = 5;
+pub const HEIGHT_PITCH_STEPS: usize = 5;
+pub const BALL_TONE_FREQUENCY: f32 = 220.0;
+pub const BALL_TONE_PITCH_STEPS: usize = 13;
+pub const BALL_TONE_SEMITONES_PER_STEP: f32 = 2.0;
+pub const BALL_TONE_LOOP_TIME: f32 = 0.25;
+pub const BALL_TONE_FADE_TIME: f32 = 0.05;
+pub const MUSIC_CROSSFADE_TIME: f32 = 1.5;
+pub const MUSIC_LAYER_FADE_TIME: f32 = 0.6;
+pub const MUSIC_DROPOUT_FADE_TIME: f32 = 0.1;
+pub const MUSIC_STING_TIME: f32 = 1.5;
+pub const MUSIC_FULL_RALLY: u32 = 12;
+pub const MUSIC_BASS_INTENSITY: f32 = 0.25;
+pub const MUSIC_LEAD_INTENSITY: f32 = 0.6;
+pub const MUSIC_MATCH_POINT_SHIFT: f32 = 2.0;
+pub const HIT_PITCH_STEPS: usize = 17;
+pub const WALL_PITCH_STEPS: usize = 13;
+
+pub const ARENA_DIR: &str = "arenas";
+pub const MIN_OBSTACLE_SIZE: f32 = 10.0;
+pub const EDITOR_GRID: f32 = 5.0;
 
 pub const PARTICLE_COUNT: usize = 15;
+pub const EFFECTS_STEP: f32 = 0.25;
 pub const TRAIL_LENGTH: usize = 10;
 
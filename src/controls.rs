@@ -0,0 +1,110 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const BINDABLE_KEYS: [KeyCode; 57] = [
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G, KeyCode::H, KeyCode::I,
+    KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N, KeyCode::O, KeyCode::Q, KeyCode::R, KeyCode::S,
+    KeyCode::T, KeyCode::U, KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right,
+    KeyCode::Space, KeyCode::Enter, KeyCode::Tab, KeyCode::LeftShift, KeyCode::RightShift,
+    KeyCode::LeftControl, KeyCode::RightControl, KeyCode::LeftAlt, KeyCode::RightAlt,
+    KeyCode::Comma, KeyCode::Period, KeyCode::Slash, KeyCode::Semicolon, KeyCode::Apostrophe,
+    KeyCode::LeftBracket, KeyCode::RightBracket, KeyCode::Minus, KeyCode::Equal,
+];
+
+pub const BINDING_NAMES: [&str; 5] = ["UP", "DOWN", "SERVE", "BACK", "FORWARD"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct KeyBindings {
+    #[serde(with = "key_code")]
+    pub negative: KeyCode,
+    #[serde(with = "key_code")]
+    pub positive: KeyCode,
+    #[serde(with = "key_code")]
+    pub launch: KeyCode,
+    #[serde(with = "key_code")]
+    pub retreat: KeyCode,
+    #[serde(with = "key_code")]
+    pub advance: KeyCode,
+}
+
+impl KeyBindings {
+    pub fn for_seat(seat: usize) -> Self {
+        match seat {
+            0 => Self {
+                negative: KeyCode::W,
+                positive: KeyCode::S,
+                launch: KeyCode::E,
+                retreat: KeyCode::A,
+                advance: KeyCode::D,
+            },
+            1 => Self {
+                negative: KeyCode::Up,
+                positive: KeyCode::Down,
+                launch: KeyCode::Enter,
+                retreat: KeyCode::Right,
+                advance: KeyCode::Left,
+            },
+            2 => Self {
+                negative: KeyCode::F,
+                positive: KeyCode::G,
+                launch: KeyCode::R,
+                retreat: KeyCode::T,
+                advance: KeyCode::V,
+            },
+            _ => Self {
+                negative: KeyCode::K,
+                positive: KeyCode::L,
+                launch: KeyCode::O,
+                retreat: KeyCode::Comma,
+                advance: KeyCode::I,
+            },
+        }
+    }
+
+    pub fn set(&mut self, index: usize, key: KeyCode) {
+        match index {
+            0 => self.negative = key,
+            1 => self.positive = key,
+            2 => self.launch = key,
+            3 => self.retreat = key,
+            _ => self.advance = key,
+        }
+    }
+
+    pub fn keys_down(&self) -> (bool, bool) {
+        (is_key_down(self.negative), is_key_down(self.positive))
+    }
+
+    pub fn depth_keys_down(&self) -> (bool, bool) {
+        (is_key_down(self.retreat), is_key_down(self.advance))
+    }
+
+    pub fn movement_label(&self) -> String {
+        format!("{} / {}", key_name(self.negative), key_name(self.positive))
+    }
+}
+
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key).to_uppercase()
+}
+
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.iter().copied().find(|key| key_name(*key) == name)
+}
+
+mod key_code {
+    use macroquad::prelude::KeyCode;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(key: &KeyCode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::key_name(*key))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyCode, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        super::key_from_name(&name).ok_or_else(|| serde::de::Error::custom(format!("unknown key {}", name)))
+    }
+}
@@ -0,0 +1,329 @@
+use macroquad::prelude::*;
+use crate::consts::*;
+use crate::effects::*;
+use crate::arena::{Arena, Motion, Obstacle, Shape, Spawn};
+
+pub enum EditorAction {
+    None,
+    TestPlay(Arena),
+    Exit,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Drag {
+    Move(Vec2),
+    Resize,
+    Spawn(usize),
+}
+
+pub struct Editor {
+    arena: Arena,
+    selected: Option<usize>,
+    drag: Option<Drag>,
+    renaming: bool,
+    status: String,
+    status_time: f32,
+    phase: f32,
+    load_index: usize,
+}
+
+impl Editor {
+    pub fn new(arena: Arena) -> Self {
+        Self {
+            arena,
+            selected: None,
+            drag: None,
+            renaming: false,
+            status: String::new(),
+            status_time: 0.0,
+            phase: 0.0,
+            load_index: 0,
+        }
+    }
+
+    fn set_status(&mut self, status: String) {
+        self.status = status;
+        self.status_time = 3.0;
+    }
+
+    fn obstacle_at(&self, point: Vec2) -> Option<usize> {
+        self.arena.obstacles.iter().rposition(|obstacle| obstacle.contains(point))
+    }
+
+    fn spawn_at(&self, point: Vec2) -> Option<usize> {
+        self.arena
+            .spawns
+            .iter()
+            .position(|spawn| Vec2::new(spawn.x, spawn.y).distance(point) < 12.0)
+    }
+
+    fn add_obstacle(&mut self, shape: Shape, position: Vec2) {
+        let (width, height, radius) = match shape {
+            Shape::Rect => (30.0, 90.0, 0.0),
+            Shape::Circle => (0.0, 0.0, 30.0),
+            Shape::Bar => (140.0, 12.0, 0.0),
+        };
+        self.arena.obstacles.push(Obstacle {
+            shape,
+            x: position.x,
+            y: position.y,
+            width,
+            height,
+            radius,
+            angle: 0.0,
+            spin: 0.0,
+            motion: None,
+        });
+        self.selected = Some(self.arena.obstacles.len() - 1);
+    }
+
+    fn update_rename(&mut self) {
+        while let Some(c) = get_char_pressed() {
+            if (c.is_ascii_alphanumeric() || c == ' ' || c == '-') && self.arena.name.len() < 20 {
+                self.arena.name.push(c);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.arena.name.pop();
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Escape) {
+            if self.arena.name.trim().is_empty() {
+                self.arena.name = "Custom".to_string();
+            }
+            self.renaming = false;
+        }
+    }
+
+    fn update_mouse(&mut self) {
+        let mouse = mouse_world();
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let on_handle = self.selected.is_some_and(|index| {
+                self.arena.obstacles[index].resize_handle().distance(mouse) < 10.0
+            });
+            if on_handle {
+                self.drag = Some(Drag::Resize);
+            } else if let Some(index) = self.spawn_at(mouse) {
+                self.drag = Some(Drag::Spawn(index));
+            } else {
+                self.selected = self.obstacle_at(mouse);
+                self.drag = self.selected.map(|index| {
+                    let obstacle = &self.arena.obstacles[index];
+                    Drag::Move(Vec2::new(obstacle.x, obstacle.y) - mouse)
+                });
+            }
+        }
+
+        if is_mouse_button_down(MouseButton::Left) {
+            let snapped = (mouse / EDITOR_GRID).round() * EDITOR_GRID;
+            match (self.drag, self.selected) {
+                (Some(Drag::Move(offset)), Some(index)) => {
+                    let target = ((mouse + offset) / EDITOR_GRID).round() * EDITOR_GRID;
+                    let obstacle = &mut self.arena.obstacles[index];
+                    obstacle.x = target.x.clamp(0.0, SCREEN_WIDTH);
+                    obstacle.y = target.y.clamp(0.0, SCREEN_HEIGHT);
+                }
+                (Some(Drag::Resize), Some(index)) => self.arena.obstacles[index].resize_to(snapped),
+                (Some(Drag::Spawn(index)), _) => {
+                    self.arena.spawns[index] = Spawn { x: snapped.x, y: snapped.y };
+                }
+                _ => {}
+            }
+        } else {
+            self.drag = None;
+        }
+
+        if is_mouse_button_pressed(MouseButton::Right) {
+            if let Some(index) = self.spawn_at(mouse) {
+                self.arena.spawns.remove(index);
+            } else if let Some(index) = self.obstacle_at(mouse) {
+                self.arena.obstacles.remove(index);
+                self.selected = None;
+            }
+        }
+
+        let wheel = mouse_wheel().1;
+        if let (Some(index), true) = (self.selected, wheel != 0.0) {
+            self.arena.obstacles[index].scale(if wheel > 0.0 { 1.1 } else { 0.9 });
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) -> EditorAction {
+        self.phase = (self.phase + dt * 40.0) % 360.0;
+        self.status_time -= dt;
+
+        if self.renaming {
+            self.update_rename();
+            return EditorAction::None;
+        }
+
+        self.update_mouse();
+
+        let mouse = mouse_world();
+        let control = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+
+        if is_key_pressed(KeyCode::Key1) {
+            self.add_obstacle(Shape::Rect, mouse);
+        }
+        if is_key_pressed(KeyCode::Key2) {
+            self.add_obstacle(Shape::Circle, mouse);
+        }
+        if is_key_pressed(KeyCode::Key3) {
+            self.add_obstacle(Shape::Bar, mouse);
+        }
+        if is_key_pressed(KeyCode::P) {
+            self.arena.spawns.push(Spawn { x: mouse.x, y: mouse.y });
+        }
+
+        if let Some(index) = self.selected {
+            let obstacle = &mut self.arena.obstacles[index];
+            if is_key_pressed(KeyCode::Q) && obstacle.shape == Shape::Bar {
+                obstacle.angle -= 15.0;
+            }
+            if is_key_pressed(KeyCode::E) && obstacle.shape == Shape::Bar {
+                obstacle.angle += 15.0;
+            }
+            if is_key_pressed(KeyCode::R) && obstacle.shape == Shape::Bar {
+                obstacle.spin = if obstacle.spin == 0.0 { 60.0 } else { 0.0 };
+            }
+            if is_key_pressed(KeyCode::M) {
+                obstacle.motion = match obstacle.motion {
+                    None => Some(Motion { dx: 0.0, dy: 100.0, period: 4.0 }),
+                    Some(_) => None,
+                };
+            }
+            if is_key_pressed(KeyCode::Delete) || is_key_pressed(KeyCode::Backspace) {
+                self.arena.obstacles.remove(index);
+                self.selected = None;
+            }
+        }
+
+        if is_key_pressed(KeyCode::Up) {
+            self.arena.goal_size = (self.arena.goal_size + 20.0).min(SCREEN_HEIGHT);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.arena.goal_size = (self.arena.goal_size - 20.0).max(BALL_SIZE * 4.0);
+        }
+
+        if is_key_pressed(KeyCode::N) {
+            self.renaming = true;
+            while get_char_pressed().is_some() {}
+        }
+
+        if control && is_key_pressed(KeyCode::S) {
+            let saved = self.arena.save();
+            match saved {
+                Ok(path) => self.set_status(format!("Saved {}", path)),
+                Err(err) => self.set_status(format!("Save failed: {}", err)),
+            }
+        }
+
+        if is_key_pressed(KeyCode::L) {
+            let arenas = Arena::load_all();
+            if !arenas.is_empty() {
+                self.load_index = (self.load_index + 1) % arenas.len();
+                self.arena = arenas[self.load_index].clone();
+                self.selected = None;
+                self.set_status(format!("Loaded {}", self.arena.name));
+            }
+        }
+
+        if is_key_pressed(KeyCode::T) {
+            return EditorAction::TestPlay(self.arena.clone());
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            return EditorAction::Exit;
+        }
+
+        EditorAction::None
+    }
+
+    pub fn draw(&self) {
+        let grid_color = Color::new(0.2, 0.2, 0.3, 0.4);
+        let mut x = 0.0;
+        while x <= SCREEN_WIDTH {
+            draw_line(x, 0.0, x, SCREEN_HEIGHT, 1.0, grid_color);
+            x += EDITOR_GRID * 4.0;
+        }
+        let mut y = 0.0;
+        while y <= SCREEN_HEIGHT {
+            draw_line(0.0, y, SCREEN_WIDTH, y, 1.0, grid_color);
+            y += EDITOR_GRID * 4.0;
+        }
+
+        self.arena.draw(0.0, self.phase);
+
+        for (i, obstacle) in self.arena.obstacles.iter().enumerate() {
+            if let Some(motion) = obstacle.motion {
+                let start = Vec2::new(obstacle.x - motion.dx, obstacle.y - motion.dy);
+                let end = Vec2::new(obstacle.x + motion.dx, obstacle.y + motion.dy);
+                draw_line(start.x, start.y, end.x, end.y, 1.0, Color::new(1.0, 1.0, 1.0, 0.4));
+            }
+            if Some(i) == self.selected {
+                let handle = obstacle.resize_handle();
+                draw_circle_lines(obstacle.x, obstacle.y, 6.0, 2.0, WHITE);
+                draw_rectangle(handle.x - 5.0, handle.y - 5.0, 10.0, 10.0, WHITE);
+            }
+        }
+
+        for spawn in &self.arena.spawns {
+            let color = get_rainbow_color((self.phase + 120.0) % 360.0);
+            draw_circle_lines(spawn.x, spawn.y, 10.0, 2.0, color);
+            draw_circle(spawn.x, spawn.y, 3.0, color);
+        }
+
+        let title = if self.renaming {
+            format!("NAME: {}_", self.arena.name)
+        } else {
+            format!("EDITING: {}", self.arena.name.to_uppercase())
+        };
+        let title_size = 24.0;
+        draw_text_ex(
+            &title,
+            SCREEN_WIDTH / 2.0 - measure_text(&title, None, title_size as u16, 1.0).width / 2.0,
+            30.0,
+            TextParams {
+                font: None,
+                font_size: title_size as u16,
+                color: get_rainbow_color(self.phase),
+                ..Default::default()
+            },
+        );
+
+        let help = [
+            "1/2/3: ADD RECT/CIRCLE/BAR   P: ADD SPAWN   DRAG: MOVE   HANDLE/WHEEL: RESIZE",
+            "Q/E: ROTATE   R: SPIN BAR   M: MOVING   DEL/RIGHT CLICK: DELETE   UP/DOWN: GOAL SIZE",
+            "T: TEST PLAY   CTRL+S: SAVE   L: LOAD NEXT   N: RENAME   ESC: MENU",
+        ];
+        let help_size = 14.0;
+        for (i, line) in help.iter().enumerate() {
+            draw_text_ex(
+                line,
+                SCREEN_WIDTH / 2.0 - measure_text(line, None, help_size as u16, 1.0).width / 2.0,
+                SCREEN_HEIGHT - 50.0 + i as f32 * 16.0,
+                TextParams {
+                    font: None,
+                    font_size: help_size as u16,
+                    color: Color::new(0.8, 0.8, 0.8, 0.8),
+                    ..Default::default()
+                },
+            );
+        }
+
+        if self.status_time > 0.0 {
+            let status_size = 18.0;
+            draw_text_ex(
+                &self.status,
+                SCREEN_WIDTH / 2.0 - measure_text(&self.status, None, status_size as u16, 1.0).width / 2.0,
+                55.0,
+                TextParams {
+                    font: None,
+                    font_size: status_size as u16,
+                    color: Color::new(1.0, 1.0, 1.0, self.status_time.min(1.0)),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
@@ -1,4 +1,32 @@
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::consts::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EffectSettings {
+    pub intensity: f32,
+    pub screen_shake: bool,
+}
+
+impl Default for EffectSettings {
+    fn default() -> Self {
+        Self {
+            intensity: 1.0,
+            screen_shake: true,
+        }
+    }
+}
+
+impl EffectSettings {
+    pub fn particles(&self, count: usize) -> usize {
+        (count as f32 * self.intensity).round() as usize
+    }
+
+    pub fn shake(&self, amount: f32) -> f32 {
+        if self.screen_shake { amount * self.intensity } else { 0.0 }
+    }
+}
 
 pub struct Particle {
     pub position: Vec2,
@@ -73,6 +101,22 @@ impl Trail {
         self.points.retain(|p| p.time > 0.0);
     }
 
+    pub fn draw_ribbon(&self, phase: f32) {
+        for pair in self.points.windows(2) {
+            let alpha = pair[1].time * 0.5;
+            let color_hue = (pair[1].hue + phase) % 360.0;
+            let color = hsv_to_rgb(color_hue / 360.0, 1.0, 1.0);
+            draw_line(
+                pair[0].position.x,
+                pair[0].position.y,
+                pair[1].position.x,
+                pair[1].position.y,
+                6.0 * pair[1].time,
+                Color::new(color.0, color.1, color.2, alpha),
+            );
+        }
+    }
+
     pub fn draw(&self, phase: f32) {
         for point in &self.points {
             let alpha = point.time * 0.6;
@@ -129,6 +173,73 @@ pub fn draw_glow(position: Vec2, size: f32, color: Color, intensity: f32) {
     }
 }
 
+pub fn shake_offset(shake: f32) -> Vec2 {
+    if shake <= 0.0 {
+        return Vec2::ZERO;
+    }
+    Vec2::new(
+        macroquad::rand::gen_range(-10.0, 10.0) * shake,
+        macroquad::rand::gen_range(-10.0, 10.0) * shake,
+    )
+}
+
+fn screen_camera(offset: Vec2) -> Camera2D {
+    let scale = (screen_width() / SCREEN_WIDTH).min(screen_height() / SCREEN_HEIGHT);
+    let (width, height) = (SCREEN_WIDTH * scale, SCREEN_HEIGHT * scale);
+    Camera2D {
+        target: Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0) - offset,
+        zoom: Vec2::new(2.0 / SCREEN_WIDTH, 2.0 / SCREEN_HEIGHT),
+        viewport: Some((
+            ((screen_width() - width) / 2.0) as i32,
+            ((screen_height() - height) / 2.0) as i32,
+            width as i32,
+            height as i32,
+        )),
+        ..Default::default()
+    }
+}
+
+pub fn set_screen_camera() {
+    set_camera(&screen_camera(Vec2::ZERO));
+}
+
+pub fn set_shake_camera(offset: Vec2) {
+    set_camera(&screen_camera(offset));
+}
+
+pub fn mouse_world() -> Vec2 {
+    screen_camera(Vec2::ZERO).screen_to_world(Vec2::from(mouse_position()))
+}
+
+pub fn draw_background(phase: f32) {
+    let top = get_rainbow_color(phase);
+    let bottom = get_rainbow_color((phase + 180.0) % 360.0);
+    for y in 0..SCREEN_HEIGHT as i32 {
+        let t = y as f32 / SCREEN_HEIGHT;
+        let color = Color::new(
+            top.r + (bottom.r - top.r) * t,
+            top.g + (bottom.g - top.g) * t,
+            top.b + (bottom.b - top.b) * t,
+            0.05,
+        );
+        draw_line(0.0, y as f32, SCREEN_WIDTH, y as f32, 2.0, color);
+    }
+}
+
+pub fn draw_centered_text(text: &str, x: f32, y: f32, size: f32, color: Color) {
+    draw_text_ex(
+        text,
+        x - measure_text(text, None, size as u16, 1.0).width / 2.0,
+        y,
+        TextParams {
+            font: None,
+            font_size: size as u16,
+            color,
+            ..Default::default()
+        },
+    );
+}
+
 pub fn create_particle_explosion(position: Vec2, hue: f32, count: usize) -> Vec<Particle> {
     use crate::consts::PARTICLE_COUNT;
     let mut particles = Vec::new();
@@ -0,0 +1,119 @@
+use macroquad::prelude::*;
+use crate::powerups::PowerUpKind;
+use crate::rules::Side;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameEvent {
+    Serve { server: Side },
+    PaddleHit { side: Side, position: Vec2, hue: f32, speed: f32, offset: f32 },
+    WallBounce { side: Side, position: Vec2, hue: f32, speed: f32 },
+    ObstacleHit { position: Vec2, hue: f32 },
+    BallCollision { position: Vec2, hue: f32 },
+    BrickHit { position: Vec2, hue: f32, destroyed: bool },
+    ShieldBounce { position: Vec2 },
+    PowerUpCollected { kind: PowerUpKind, position: Vec2 },
+    SpeedCapReached { position: Vec2, hue: f32 },
+    BallOut { conceding: Side, position: Vec2, hue: f32 },
+    Score { scorer: Side, server: Side },
+    LifeLost { side: Side, remaining: u32 },
+    Eliminated { side: Side, position: Vec2, hue: f32 },
+    GameWon { side: Side },
+    MatchPoint { side: Side },
+    MatchEnd { winner: Side },
+}
+
+impl GameEvent {
+    pub fn position(&self) -> Option<Vec2> {
+        match *self {
+            GameEvent::PaddleHit { position, .. }
+            | GameEvent::WallBounce { position, .. }
+            | GameEvent::ObstacleHit { position, .. }
+            | GameEvent::BallCollision { position, .. }
+            | GameEvent::BrickHit { position, .. }
+            | GameEvent::ShieldBounce { position }
+            | GameEvent::PowerUpCollected { position, .. }
+            | GameEvent::SpeedCapReached { position, .. }
+            | GameEvent::BallOut { position, .. }
+            | GameEvent::Eliminated { position, .. } => Some(position),
+            _ => None,
+        }
+    }
+}
+
+pub trait EventListener {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+#[derive(Default)]
+pub struct EventBus {
+    events: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn emit(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    pub fn dispatch(&self, listener: &mut impl EventListener) {
+        for event in &self.events {
+            listener.on_event(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        seen: Vec<GameEvent>,
+    }
+
+    impl EventListener for Recorder {
+        fn on_event(&mut self, event: &GameEvent) {
+            self.seen.push(*event);
+        }
+    }
+
+    #[test]
+    fn dispatch_delivers_every_event_in_order_until_cleared() {
+        let mut bus = EventBus::default();
+        let events = [
+            GameEvent::Serve { server: Side::Left },
+            GameEvent::WallBounce { side: Side::Top, position: Vec2::new(1.0, 2.0), hue: 0.0, speed: 300.0 },
+            GameEvent::Score { scorer: Side::Right, server: Side::Left },
+        ];
+        for event in events {
+            bus.emit(event);
+        }
+
+        let (mut first, mut second) = (Recorder::default(), Recorder::default());
+        bus.dispatch(&mut first);
+        bus.dispatch(&mut second);
+        assert_eq!(first.seen, events);
+        assert_eq!(second.seen, events);
+        assert_eq!(bus.events(), events);
+
+        bus.clear();
+        let mut late = Recorder::default();
+        bus.dispatch(&mut late);
+        assert!(late.seen.is_empty());
+    }
+
+    #[test]
+    fn only_events_that_happen_somewhere_have_a_position() {
+        let at = Vec2::new(10.0, 20.0);
+        assert_eq!(GameEvent::ShieldBounce { position: at }.position(), Some(at));
+        assert_eq!(GameEvent::BallOut { conceding: Side::Left, position: at, hue: 0.0 }.position(), Some(at));
+        assert_eq!(GameEvent::MatchEnd { winner: Side::Left }.position(), None);
+    }
+}
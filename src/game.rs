@@ -1,138 +1,623 @@
 use macroquad::prelude::*;
 use crate::consts::*;
 use crate::paddle::Paddle;
-use crate::ball::Ball;
+use crate::ball::{resolve_ball_collisions, Ball};
 use crate::effects::*;
+use crate::rules::{GameMode, MatchRules, MatchScore, PointEnd, ScoreOutcome, Side};
+use crate::controls::{key_name, KeyBindings};
+use crate::bricks::BrickWall;
+use crate::powerups::{PowerUpKind, PowerUpSystem};
+use crate::arena::Arena;
+use crate::stats::MatchStats;
+use crate::profiles::Profile;
+use crate::achievements::AchievementTracker;
+use crate::events::{EventBus, GameEvent};
+use crate::settings::Settings;
 
 pub struct Game {
-    left_paddle: Paddle,
-    right_paddle: Paddle,
-    ball: Ball,
-    left_score: u32,
-    right_score: u32,
+    paddles: Vec<Paddle>,
+    sides: Vec<Side>,
+    lives: [u32; 4],
+    balls: Vec<Ball>,
+    score: MatchScore,
     phase: f32,
     particles: Vec<Particle>,
     screen_shake: f32,
     shake_offset: Vec2,
+    effects: EffectSettings,
+    serve: Option<ServeState>,
+    first_server: Side,
+    power_ups: PowerUpSystem,
+    arena: Arena,
+    arena_time: f32,
+    bricks: Option<BrickWall>,
+    stats: MatchStats,
+    profiles: Vec<SeatedProfile>,
+    achievements: AchievementTracker,
+    events: EventBus,
+}
+
+pub struct SeatedProfile {
+    pub index: usize,
+    pub side: Side,
+    pub name: String,
+}
+
+#[derive(Clone, Copy)]
+struct ServeState {
+    server: Side,
+    countdown: f32,
+    spawn: Vec2,
+    aim: f32,
+    aim_direction: f32,
 }
 
 impl Game {
-    pub fn new(two_players: bool) -> Self {
+    pub fn new(humans: [bool; 4], rules: MatchRules, arena: Arena) -> Self {
+        let seats = rules.mode.seats();
+        let mut sides: Vec<Side> = Vec::new();
+        for seat in &seats {
+            if !sides.contains(&seat.side) {
+                sides.push(seat.side);
+            }
+        }
+        let four_sided = sides.len() == 4;
+        let corner = PADDLE_MARGIN + PADDLE_WIDTH + 5.0;
+        let paddles = seats
+            .iter()
+            .enumerate()
+            .map(|(i, seat)| {
+                let mut paddle = Paddle::new(seat.side, seat.depth, !humans[i], KeyBindings::for_seat(i));
+                paddle.ai = rules.ai;
+                paddle.set_zone_depth(rules.paddle_zone);
+                if seat.depth > 0.0 {
+                    paddle.hue = (paddle.hue + 40.0) % 360.0;
+                }
+                if four_sided {
+                    paddle.travel.0 = corner;
+                    paddle.travel.1 -= corner;
+                }
+                paddle
+            })
+            .collect();
+        let first_server = sides[macroquad::rand::gen_range(0, sides.len())];
+        let four_player = rules.mode == GameMode::FourPlayer;
+        let stats = MatchStats::new(sides.clone(), four_player, if four_player { [rules.lives; 4] } else { [0; 4] });
+
         let mut game = Self {
-            left_paddle: Paddle::new(PADDLE_MARGIN + PADDLE_WIDTH / 2.0, false),
-            right_paddle: Paddle::new(SCREEN_WIDTH - PADDLE_MARGIN - PADDLE_WIDTH / 2.0, !two_players),
-            ball: Ball::new(),
-            left_score: 0,
-            right_score: 0,
+            paddles,
+            sides,
+            lives: [rules.lives; 4],
+            balls: Vec::new(),
+            score: MatchScore::new(rules),
             phase: 0.0,
             particles: Vec::new(),
             screen_shake: 0.0,
             shake_offset: Vec2::ZERO,
+            effects: EffectSettings::default(),
+            serve: None,
+            first_server,
+            power_ups: PowerUpSystem::new(rules.power_ups),
+            arena,
+            arena_time: 0.0,
+            bricks: (rules.mode == GameMode::Breakout).then(BrickWall::new),
+            stats,
+            profiles: Vec::new(),
+            achievements: AchievementTracker::new(rules.mode),
+            events: EventBus::default(),
         };
-        game.ball.reset();
+        game.start_serve();
         game
     }
 
-    pub fn reset_ball(&mut self) {
-        self.ball.reset();
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.effects = settings.effects;
+        for (paddle, bindings) in self.paddles.iter_mut().zip(settings.bindings) {
+            if !paddle.is_ai {
+                paddle.bindings = bindings;
+            }
+        }
     }
 
-    pub fn update(&mut self, dt: f32) -> GameResult {
-        self.phase += dt * 50.0;
-        if self.phase >= 360.0 {
-            self.phase -= 360.0;
+    pub fn assign_profile(&mut self, seat: usize, index: usize, profile: &Profile) {
+        let Some(paddle) = self.paddles.get_mut(seat) else {
+            return;
+        };
+        if paddle.is_ai {
+            return;
         }
+        paddle.hue = profile.hue;
+        if let Some(bindings) = profile.bindings {
+            paddle.bindings = bindings;
+        }
+        self.achievements.add_player(index, paddle.side, profile);
+        self.profiles.push(SeatedProfile {
+            index,
+            side: paddle.side,
+            name: profile.name.clone(),
+        });
+    }
 
-        if self.screen_shake > 0.0 {
-            self.screen_shake -= dt * 5.0;
-            self.shake_offset = Vec2::new(
-                (macroquad::rand::gen_range(-10.0, 10.0)) * self.screen_shake,
-                (macroquad::rand::gen_range(-10.0, 10.0)) * self.screen_shake,
-            );
+    pub fn seated_profiles(&self) -> &[SeatedProfile] {
+        &self.profiles
+    }
+
+    pub fn unlocked_achievements(&self) -> &[(usize, String)] {
+        self.achievements.unlocked()
+    }
+
+    pub fn stats(&self) -> &MatchStats {
+        &self.stats
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.score.rules.mode
+    }
+
+    pub fn winner_title(&self, side: Side) -> String {
+        let names: Vec<&str> = self
+            .profiles
+            .iter()
+            .filter(|seated| seated.side == side)
+            .map(|seated| seated.name.as_str())
+            .collect();
+        if names.is_empty() {
+            format!("{} PLAYER WINS!", side.label())
         } else {
-            self.shake_offset = Vec2::ZERO;
+            format!("{} WINS!", names.join(" & "))
         }
+    }
 
-        let left_keys = (is_key_down(KeyCode::W), is_key_down(KeyCode::S));
-        let right_keys = (is_key_down(KeyCode::Up), is_key_down(KeyCode::Down));
+    fn is_four_player(&self) -> bool {
+        self.score.rules.mode == GameMode::FourPlayer
+    }
 
-        let ball_pos_for_ai = if self.right_paddle.is_ai {
-            Some(self.ball.position)
-        } else {
-            None
+    fn walls(&self) -> [bool; 4] {
+        Side::ALL.map(|side| self.paddle(side).is_none())
+    }
+
+    fn next_server(&self) -> Side {
+        if !self.is_four_player() {
+            return self.score.server(self.first_server);
+        }
+        let lost: u32 = self.lives.iter().map(|lives| self.score.rules.lives - lives).sum();
+        let alive: Vec<Side> = self.paddles.iter().map(|paddle| paddle.side).collect();
+        let first = alive.iter().position(|side| *side == self.first_server).unwrap_or(0);
+        alive[(first + lost as usize) % alive.len()]
+    }
+
+    fn start_serve(&mut self) {
+        self.balls.truncate(1);
+        if self.balls.is_empty() {
+            let mut ball = Ball::new();
+            ball.spin_enabled = self.score.rules.spin;
+            ball.any_angle = self.is_four_player();
+            self.balls.push(ball);
+        }
+        self.balls[0].reset();
+        let server = self.next_server();
+        let spawn = match (&self.bricks, self.paddle(server)) {
+            (Some(_), Some(paddle)) => paddle.home + server.normal() * 120.0,
+            _ => self.arena.spawn_point(),
+        };
+        self.serve = Some(ServeState {
+            server,
+            countdown: SERVE_COUNTDOWN,
+            spawn,
+            aim: 0.0,
+            aim_direction: 1.0,
+        });
+    }
+
+    fn paddle(&self, side: Side) -> Option<&Paddle> {
+        self.paddles.iter().find(|paddle| paddle.side == side)
+    }
+
+    fn is_manual_serve(&self, server: Side) -> bool {
+        self.score.rules.manual_serve && self.paddle(server).is_some_and(|paddle| !paddle.is_ai)
+    }
+
+    fn update_serve(&mut self, dt: f32) -> bool {
+        let Some(mut serve) = self.serve else {
+            return false;
         };
 
-        self.left_paddle.update(dt, None, left_keys);
-        self.right_paddle.update(dt, ball_pos_for_ai, right_keys);
+        let direction = serve.server.normal();
+        let rest = match self.paddle(serve.server) {
+            Some(paddle) if self.score.rules.manual_serve => {
+                paddle.position + direction * (PADDLE_WIDTH / 2.0 + BALL_SIZE)
+            }
+            _ => serve.spawn,
+        };
+        self.balls[0].hold(dt, rest);
 
-        if let Some(collision) = self.ball.update(dt, &self.left_paddle, &self.right_paddle) {
-            self.screen_shake = 0.3;
-            let explosion = create_particle_explosion(collision.position, collision.hue, 10);
-            self.particles.extend(explosion);
+        serve.countdown -= dt;
+        serve.aim += serve.aim_direction * SERVE_AIM_SPEED * dt;
+        if serve.aim.abs() > SERVE_MAX_ANGLE {
+            serve.aim = serve.aim.clamp(-SERVE_MAX_ANGLE, SERVE_MAX_ANGLE);
+            serve.aim_direction = -serve.aim_direction;
         }
 
-        self.particles.retain_mut(|p| {
-            p.update(dt);
-            p.is_alive()
-        });
+        let manual = self.is_manual_serve(serve.server);
+        let launch = if serve.countdown > 0.0 {
+            false
+        } else if manual {
+            self.paddle(serve.server).is_some_and(|paddle| is_key_pressed(paddle.bindings.launch))
+        } else {
+            true
+        };
 
-        if let Some(scored_right) = self.ball.scored() {
-            if scored_right {
-                self.right_score += 1;
-                if self.right_score >= WIN_SCORE {
-                    return GameResult::RightWins;
-                }
+        if launch {
+            let angle = if manual {
+                serve.aim
             } else {
-                self.left_score += 1;
-                if self.left_score >= WIN_SCORE {
-                    return GameResult::LeftWins;
+                macroquad::rand::gen_range(-SERVE_MAX_ANGLE, SERVE_MAX_ANGLE)
+            };
+            self.balls[0].launch(direction, angle.to_radians());
+            for i in 1..self.score.rules.serve_balls() {
+                let spread = MULTI_BALL_SPREAD * i.div_ceil(2) as f32;
+                let offset = if i % 2 == 1 { spread } else { -spread };
+                let extra = self.balls[0].split(offset);
+                self.balls.push(extra);
+            }
+            self.events.emit(GameEvent::Serve { server: serve.server });
+            self.serve = None;
+            false
+        } else {
+            self.serve = Some(serve);
+            true
+        }
+    }
+
+    fn update_power_ups(&mut self, dt: f32) {
+        let spawned = self.power_ups.update(dt, &self.effects);
+        self.particles.extend(spawned);
+
+        for ball in &mut self.balls {
+            for side in Side::ALL {
+                if self.power_ups.is_active(side, PowerUpKind::Shield) && ball.bounce_off_shield(side) {
+                    self.events.emit(GameEvent::ShieldBounce { position: ball.position });
                 }
             }
-            self.reset_ball();
         }
 
-        GameResult::Continue
+        let mut split_from = Vec::new();
+        for (i, ball) in self.balls.iter().enumerate() {
+            let Some(collector) = ball.last_touched else {
+                continue;
+            };
+            if let Some((kind, position)) = self.power_ups.collect(ball.get_rect(), collector) {
+                self.events.emit(GameEvent::PowerUpCollected { kind, position });
+                if kind == PowerUpKind::MultiBall {
+                    split_from.push(i);
+                }
+            }
+        }
+
+        for i in split_from {
+            let left = self.balls[i].split(MULTI_BALL_SPREAD);
+            let right = self.balls[i].split(-MULTI_BALL_SPREAD);
+            self.balls.push(left);
+            self.balls.push(right);
+        }
     }
 
-    pub fn draw(&self) {
-        set_default_camera();
-        
-        if self.shake_offset.length() > 0.0 {
-            set_camera(&Camera2D {
-                target: self.shake_offset,
-                ..Default::default()
+    fn most_threatening_ball(&self, paddle: &Paddle) -> Option<&Ball> {
+        let normal = paddle.side.normal();
+        let distance = |ball: &Ball| (ball.position - paddle.position).dot(normal);
+        let approaching = self
+            .balls
+            .iter()
+            .filter(|ball| distance(ball) > 0.0 && ball.velocity.dot(normal) < 0.0)
+            .min_by(|a, b| {
+                let time_a = distance(a) / -a.velocity.dot(normal);
+                let time_b = distance(b) / -b.velocity.dot(normal);
+                time_a.total_cmp(&time_b)
             });
+
+        approaching.or_else(|| {
+            self.balls.iter().min_by(|a, b| distance(a).abs().total_cmp(&distance(b).abs()))
+        })
+    }
+
+    fn ai_target(&self, paddle: &Paddle) -> Option<Vec2> {
+        let ball = self.most_threatening_ball(paddle)?;
+        let personality = paddle.ai.personality;
+        if personality.recenters() && ball.velocity.dot(paddle.side.normal()) >= 0.0 {
+            return Some(paddle.home);
         }
+        Some(ball.position + paddle.axis() * paddle.height() * personality.lean(self.arena_time))
+    }
 
-        let bg_color1 = get_rainbow_color(self.phase);
-        let bg_color2 = get_rainbow_color((self.phase + 180.0) % 360.0);
+    fn lose_life(&mut self, side: Side) -> Option<GameResult> {
+        let lives = &mut self.lives[side.index()];
+        *lives = lives.saturating_sub(1);
+        let remaining = *lives;
+        self.events.emit(GameEvent::LifeLost { side, remaining });
+        if remaining > 0 {
+            return None;
+        }
 
-        for y in 0..SCREEN_HEIGHT as i32 {
-            let t = y as f32 / SCREEN_HEIGHT;
-            let color = Color::new(
-                bg_color1.r + (bg_color2.r - bg_color1.r) * t,
-                bg_color1.g + (bg_color2.g - bg_color1.g) * t,
-                bg_color1.b + (bg_color2.b - bg_color1.b) * t,
-                0.05,
-            );
-            draw_line(0.0, y as f32, SCREEN_WIDTH, y as f32, 2.0, color);
+        if let Some(index) = self.paddles.iter().position(|paddle| paddle.side == side) {
+            let paddle = self.paddles.remove(index);
+            self.events.emit(GameEvent::Eliminated { side, position: paddle.position, hue: paddle.hue });
+        }
+
+        match self.paddles.as_slice() {
+            [last] => Some(GameResult::Winner(last.side)),
+            _ => None,
+        }
+    }
+
+    fn award_point(&mut self, side: Side) -> Option<GameResult> {
+        self.events.emit(GameEvent::Score { scorer: side, server: self.next_server() });
+        let outcome = self.score.award_point(side);
+        if let ScoreOutcome::Game(side) | ScoreOutcome::Set(side) = outcome {
+            self.events.emit(GameEvent::GameWon { side });
+        }
+        let finished = matches!(outcome, ScoreOutcome::Match(_));
+        if let Some(side) = self.score.match_point().filter(|_| !finished) {
+            self.events.emit(GameEvent::MatchPoint { side });
+        }
+        match outcome {
+            ScoreOutcome::Match(side) => Some(GameResult::Winner(side)),
+            _ => None,
+        }
+    }
+
+    fn update_bricks(&mut self) -> Option<GameResult> {
+        let bricks = self.bricks.as_mut()?;
+        let mut scorers = Vec::new();
+        for ball in &mut self.balls {
+            let Some(hit) = bricks.collide(ball) else {
+                continue;
+            };
+            self.events.emit(GameEvent::BrickHit { position: hit.position, hue: hit.hue, destroyed: hit.destroyed });
+            if hit.destroyed {
+                scorers.extend(ball.last_touched);
+            }
+        }
+
+        for side in scorers {
+            if let Some(result) = self.award_point(side) {
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    fn score_balls(&mut self) -> Option<GameResult> {
+        let mut index = 0;
+        while index < self.balls.len() {
+            let Some(conceding) = self.balls[index].scored() else {
+                index += 1;
+                continue;
+            };
+
+            let ball = self.balls.remove(index);
+            let exit = ball.position.clamp(Vec2::ZERO, Vec2::new(SCREEN_WIDTH, SCREEN_HEIGHT));
+            self.events.emit(GameEvent::BallOut { conceding, position: exit, hue: ball.hue });
+
+            let last_ball = self.balls.is_empty();
+            if self.score.rules.point_end == PointEnd::LastBallOut && !last_ball {
+                continue;
+            }
+
+            if self.is_four_player() {
+                if let Some(result) = self.lose_life(conceding) {
+                    return Some(result);
+                }
+                continue;
+            }
+
+            if let Some(result) = self.award_point(conceding.opponent()) {
+                return Some(result);
+            }
+        }
+
+        if self.balls.is_empty() {
+            self.start_serve();
+        }
+        None
+    }
+
+    pub fn events(&self) -> &[GameEvent] {
+        self.events.events()
+    }
+
+    pub fn ball_position(&self) -> Option<Vec2> {
+        self.balls.first().map(|ball| ball.position)
+    }
+
+    pub fn update(&mut self, dt: f32) -> GameResult {
+        self.events.clear();
+        let result = self.step(dt);
+        if let GameResult::Winner(winner) = result {
+            self.events.emit(GameEvent::MatchEnd { winner });
+        }
+
+        let events = std::mem::take(&mut self.events);
+        events.dispatch(&mut self.stats);
+        events.dispatch(&mut self.achievements);
+        for event in events.events() {
+            self.apply_effects(event);
+        }
+        self.events = events;
+
+        self.achievements.update(dt);
+        result
+    }
+
+    fn apply_effects(&mut self, event: &GameEvent) {
+        let (shake, burst) = match *event {
+            GameEvent::PaddleHit { position, hue, .. } => (0.3, Some((position, hue, 10))),
+            GameEvent::SpeedCapReached { position, hue } => (0.4, Some((position, hue, PARTICLE_COUNT))),
+            GameEvent::ObstacleHit { position, hue } => (0.15, Some((position, hue, 6))),
+            GameEvent::BallCollision { position, hue } => (0.3, Some((position, hue, 10))),
+            GameEvent::BrickHit { position, hue, destroyed: true } => (0.35, Some((position, hue, PARTICLE_COUNT))),
+            GameEvent::BrickHit { position, hue, destroyed: false } => (0.15, Some((position, hue, 5))),
+            GameEvent::ShieldBounce { position } => (0.2, Some((position, PowerUpKind::Shield.hue(), 8))),
+            GameEvent::PowerUpCollected { kind, position } => (0.25, Some((position, kind.hue(), PARTICLE_COUNT))),
+            GameEvent::BallOut { position, hue, .. } => (0.0, Some((position, hue, 8))),
+            GameEvent::Eliminated { position, hue, .. } => (0.5, Some((position, hue, PARTICLE_COUNT))),
+            GameEvent::GameWon { .. } => (0.5, None),
+            _ => (0.0, None),
+        };
+        self.screen_shake = self.screen_shake.max(self.effects.shake(shake));
+        if let Some((position, hue, count)) = burst {
+            self.particles.extend(create_particle_explosion(position, hue, self.effects.particles(count)));
+        }
+    }
+
+    fn step(&mut self, dt: f32) -> GameResult {
+        self.phase += dt * 50.0;
+        if self.phase >= 360.0 {
+            self.phase -= 360.0;
+        }
+
+        self.arena_time += dt;
+        self.stats.tick(dt);
+
+        self.screen_shake = (self.screen_shake - dt * 5.0).max(0.0);
+        self.shake_offset = shake_offset(self.screen_shake);
+
+        let targets: Vec<Option<Vec2>> = self
+            .paddles
+            .iter()
+            .map(|paddle| if paddle.is_ai { self.ai_target(paddle) } else { None })
+            .collect();
+
+        for (paddle, target) in self.paddles.iter_mut().zip(targets) {
+            let side = paddle.side;
+            let mut keys = paddle.bindings.keys_down();
+            if self.power_ups.is_active(side, PowerUpKind::ReverseControls) {
+                keys = (keys.1, keys.0);
+            }
+            paddle.height_scale = self.power_ups.paddle_scale(side);
+            paddle.update(dt, target, keys, paddle.bindings.depth_keys_down());
+        }
+
+        if self.update_serve(dt) {
+            self.particles.retain_mut(|p| {
+                p.update(dt);
+                p.is_alive()
+            });
+            return GameResult::Continue;
+        }
+
+        let ball_dt = dt * self.power_ups.ball_time_scale();
+        let walls = self.walls();
+        for ball in &mut self.balls {
+            let capped = ball.speed >= ball.speed_cap;
+            if let Some(collision) = ball.update(ball_dt, &self.paddles, walls) {
+                if let Some(side) = ball.last_touched {
+                    let offset = self
+                        .paddles
+                        .iter()
+                        .filter(|paddle| paddle.side == side)
+                        .map(|paddle| paddle.hit_offset(collision.position))
+                        .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+                        .unwrap_or(0.0);
+                    self.events.emit(GameEvent::PaddleHit {
+                        side,
+                        position: collision.position,
+                        hue: collision.hue,
+                        speed: ball.velocity.length(),
+                        offset,
+                    });
+                }
+                if !capped && ball.speed >= ball.speed_cap {
+                    self.events.emit(GameEvent::SpeedCapReached { position: collision.position, hue: collision.hue });
+                }
+            }
+            if let Some(side) = ball.wall_bounce {
+                self.events.emit(GameEvent::WallBounce {
+                    side,
+                    position: ball.position,
+                    hue: ball.hue,
+                    speed: ball.velocity.length(),
+                });
+            }
+            if let Some(collision) = self.arena.collide(self.arena_time, ball) {
+                self.events.emit(GameEvent::ObstacleHit { position: collision.position, hue: collision.hue });
+            }
+        }
+
+        for collision in resolve_ball_collisions(&mut self.balls, ball_dt) {
+            self.events.emit(GameEvent::BallCollision { position: collision.position, hue: collision.hue });
+        }
+
+        let fastest = self.balls.iter().map(|ball| ball.velocity.length()).fold(0.0, f32::max);
+        self.stats.sample_speed(ball_dt, fastest);
+
+        if let Some(result) = self.update_bricks() {
+            return result;
+        }
+
+        self.update_power_ups(dt);
+
+        self.particles.retain_mut(|p| {
+            p.update(dt);
+            p.is_alive()
+        });
+
+        if let Some(result) = self.score_balls() {
+            return result;
         }
 
+        GameResult::Continue
+    }
+
+    pub fn draw(&self) {
+        set_shake_camera(self.shake_offset);
+        draw_background(self.phase);
+
         let center_line_color = get_rainbow_color((self.phase + 90.0) % 360.0);
-        for i in 0..20 {
-            let y = (i as f32 * 30.0 + self.phase * 0.5) % SCREEN_HEIGHT;
-            draw_rectangle(
-                SCREEN_WIDTH / 2.0 - 2.0,
-                y,
+        if self.is_four_player() {
+            draw_circle_lines(
+                SCREEN_WIDTH / 2.0,
+                SCREEN_HEIGHT / 2.0,
+                80.0,
                 4.0,
-                15.0,
                 Color::new(center_line_color.r, center_line_color.g, center_line_color.b, 0.6),
             );
+        } else {
+            for i in 0..20 {
+                let y = (i as f32 * 30.0 + self.phase * 0.5) % SCREEN_HEIGHT;
+                draw_rectangle(
+                    SCREEN_WIDTH / 2.0 - 2.0,
+                    y,
+                    4.0,
+                    15.0,
+                    Color::new(center_line_color.r, center_line_color.g, center_line_color.b, 0.6),
+                );
+            }
         }
 
-        self.left_paddle.draw(self.phase);
-        self.right_paddle.draw(self.phase);
-        self.ball.draw(self.phase);
+        self.arena.draw(self.arena_time, self.phase);
+        if let Some(bricks) = &self.bricks {
+            bricks.draw(self.phase);
+        }
+        self.power_ups.draw(self.phase);
+        for paddle in &self.paddles {
+            if paddle.zone_depth > 0.0 {
+                let zone = paddle.zone_rect();
+                let zone_color = get_rainbow_color((paddle.hue + self.phase) % 360.0);
+                draw_rectangle_lines(
+                    zone.x,
+                    zone.y,
+                    zone.w,
+                    zone.h,
+                    2.0,
+                    Color::new(zone_color.r, zone_color.g, zone_color.b, 0.25),
+                );
+            }
+            paddle.draw(self.phase);
+        }
+        for ball in &self.balls {
+            ball.draw(self.phase);
+        }
+        self.draw_serve();
 
         for particle in &self.particles {
             particle.draw(self.phase);
@@ -148,41 +633,199 @@ impl Game {
         draw_rectangle(0.0, 0.0, 5.0, SCREEN_HEIGHT, border_color3);
         draw_rectangle(SCREEN_WIDTH - 5.0, 0.0, 5.0, SCREEN_HEIGHT, border_color4);
 
+        if self.is_four_player() {
+            self.draw_lives();
+            self.power_ups.draw_hud(self.phase);
+            set_screen_camera();
+            self.achievements.draw(self.phase);
+            return;
+        }
+
         let score_size = 60.0;
-        let left_score_text = format!("{}", self.left_score);
-        let right_score_text = format!("{}", self.right_score);
+        let left_score_text = format!("{}", self.score.points[0]);
+        let right_score_text = format!("{}", self.score.points[1]);
+        let left_color = get_rainbow_color((self.phase + 30.0) % 360.0);
+        let right_color = get_rainbow_color((self.phase + 210.0) % 360.0);
+        draw_centered_text(&left_score_text, SCREEN_WIDTH / 4.0, 50.0, score_size, left_color);
+        draw_centered_text(&right_score_text, SCREEN_WIDTH * 3.0 / 4.0, 50.0, score_size, right_color);
+
+        self.draw_match_progress();
+        self.power_ups.draw_hud(self.phase);
+
+        set_screen_camera();
+        self.achievements.draw(self.phase);
+    }
+
+    fn draw_lives(&self) {
+        let size = 24.0;
+        for side in self.sides.iter() {
+            let lives = self.lives[side.index()];
+            let text = if lives == 0 {
+                format!("{} OUT", side.label())
+            } else {
+                format!("{} {}", side.label(), lives)
+            };
+            let width = measure_text(&text, None, size as u16, 1.0).width;
+            let (x, y) = match side {
+                Side::Left => (PADDLE_MARGIN + 30.0, SCREEN_HEIGHT / 2.0),
+                Side::Right => (SCREEN_WIDTH - PADDLE_MARGIN - 30.0 - width, SCREEN_HEIGHT / 2.0),
+                Side::Top => (SCREEN_WIDTH / 2.0 - width / 2.0, PADDLE_MARGIN + 50.0),
+                Side::Bottom => (SCREEN_WIDTH / 2.0 - width / 2.0, SCREEN_HEIGHT - PADDLE_MARGIN - 35.0),
+            };
+            let hue = (self.phase + side.index() as f32 * 90.0) % 360.0;
+            let color = if lives == 0 { Color::new(0.5, 0.5, 0.5, 0.7) } else { get_rainbow_color(hue) };
+            draw_text_ex(
+                &text,
+                x,
+                y,
+                TextParams {
+                    font: None,
+                    font_size: size as u16,
+                    color,
+                    ..Default::default()
+                },
+            );
 
-        let left_score_hue = (self.phase + 30.0) % 360.0;
-        let right_score_hue = (self.phase + 210.0) % 360.0;
+            if lives == 0 {
+                let wall_color = get_rainbow_color((hue + 180.0) % 360.0);
+                match side {
+                    Side::Left => draw_rectangle(0.0, 0.0, 10.0, SCREEN_HEIGHT, wall_color),
+                    Side::Right => draw_rectangle(SCREEN_WIDTH - 10.0, 0.0, 10.0, SCREEN_HEIGHT, wall_color),
+                    Side::Top => draw_rectangle(0.0, 0.0, SCREEN_WIDTH, 10.0, wall_color),
+                    Side::Bottom => draw_rectangle(0.0, SCREEN_HEIGHT - 10.0, SCREEN_WIDTH, 10.0, wall_color),
+                }
+            }
+        }
+    }
+
+    fn draw_serve(&self) {
+        let Some(serve) = self.serve else {
+            return;
+        };
+
+        let manual = self.is_manual_serve(serve.server);
+        let direction = serve.server.normal();
+
+        if self.score.rules.manual_serve {
+            let aim = serve.aim.to_radians();
+            let ball = &self.balls[0];
+            let tip = ball.position + Vec2::from_angle(aim).rotate(direction) * 50.0;
+            let arrow_color = get_rainbow_color((ball.hue + self.phase) % 360.0);
+            draw_line(ball.position.x, ball.position.y, tip.x, tip.y, 3.0, arrow_color);
+            draw_circle(tip.x, tip.y, 4.0, arrow_color);
+        }
+
+        let (text, size) = if serve.countdown > 0.0 {
+            (format!("{}", serve.countdown.ceil() as u32), 90.0)
+        } else if manual {
+            let key = self.paddle(serve.server).map(|paddle| key_name(paddle.bindings.launch)).unwrap_or_default();
+            (format!("PRESS {} TO SERVE", key), 30.0)
+        } else {
+            return;
+        };
+
+        let pulse = serve.countdown.fract();
+        let hue = (self.phase + 240.0) % 360.0;
+        for offset in 0..4 {
+            let glow_size = (offset as f32) * 3.0;
+            let alpha = 0.4 * pulse.max(0.3) / (offset as f32 + 1.0);
+            let glow_color = get_rainbow_color((hue + offset as f32 * 25.0) % 360.0);
+            draw_text_ex(
+                &text,
+                SCREEN_WIDTH / 2.0 - measure_text(&text, None, size as u16, 1.0).width / 2.0 + glow_size,
+                SCREEN_HEIGHT / 2.0 - 60.0 + glow_size,
+                TextParams {
+                    font: None,
+                    font_size: size as u16,
+                    color: Color::new(glow_color.r, glow_color.g, glow_color.b, alpha),
+                    ..Default::default()
+                },
+            );
+        }
 
         draw_text_ex(
-            &left_score_text,
-            SCREEN_WIDTH / 4.0 - measure_text(&left_score_text, None, score_size as u16, 1.0).width / 2.0,
-            50.0,
+            &text,
+            SCREEN_WIDTH / 2.0 - measure_text(&text, None, size as u16, 1.0).width / 2.0,
+            SCREEN_HEIGHT / 2.0 - 60.0,
             TextParams {
                 font: None,
-                font_size: score_size as u16,
-                color: get_rainbow_color(left_score_hue),
+                font_size: size as u16,
+                color: get_rainbow_color(hue),
                 ..Default::default()
             },
         );
 
+        let server_text = match serve.server {
+            Side::Left => "< LEFT SERVES",
+            Side::Right => "RIGHT SERVES >",
+            Side::Top => "^ TOP SERVES ^",
+            Side::Bottom => "v BOTTOM SERVES v",
+        };
+        let server_size = 20.0;
         draw_text_ex(
-            &right_score_text,
-            SCREEN_WIDTH * 3.0 / 4.0 - measure_text(&right_score_text, None, score_size as u16, 1.0).width / 2.0,
-            50.0,
+            server_text,
+            SCREEN_WIDTH / 2.0 - measure_text(server_text, None, server_size as u16, 1.0).width / 2.0,
+            SCREEN_HEIGHT / 2.0 + 60.0,
             TextParams {
                 font: None,
-                font_size: score_size as u16,
-                color: get_rainbow_color(right_score_hue),
+                font_size: server_size as u16,
+                color: Color::new(0.8, 0.8, 0.8, 0.8),
                 ..Default::default()
             },
         );
+    }
+
+    fn draw_match_progress(&self) {
+        let rules = &self.score.rules;
+        let info_size = 18.0;
+        let info_color = Color::new(0.8, 0.8, 0.8, 0.8);
+
+        if rules.best_of_games > 1 || rules.best_of_sets > 1 {
+            for (i, center_x) in [SCREEN_WIDTH / 4.0, SCREEN_WIDTH * 3.0 / 4.0].iter().enumerate() {
+                let mut text = format!("GAMES {}", self.score.games[i]);
+                if rules.best_of_sets > 1 {
+                    text = format!("SETS {}  {}", self.score.sets[i], text);
+                }
+                draw_text_ex(
+                    &text,
+                    center_x - measure_text(&text, None, info_size as u16, 1.0).width / 2.0,
+                    75.0,
+                    TextParams {
+                        font: None,
+                        font_size: info_size as u16,
+                        color: info_color,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+
+        let status_text = if self.score.is_deuce() {
+            Some("DEUCE")
+        } else {
+            match self.score.advantage() {
+                Some(Side::Left) => Some("< ADVANTAGE"),
+                Some(Side::Right) => Some("ADVANTAGE >"),
+                _ => None,
+            }
+        };
 
-        set_default_camera();
+        if let Some(text) = status_text {
+            draw_text_ex(
+                text,
+                SCREEN_WIDTH / 2.0 - measure_text(text, None, info_size as u16, 1.0).width / 2.0,
+                40.0,
+                TextParams {
+                    font: None,
+                    font_size: info_size as u16,
+                    color: get_rainbow_color((self.phase + 270.0) % 360.0),
+                    ..Default::default()
+                },
+            );
+        }
     }
 
-    pub fn draw_win_screen(&self, left_won: bool) {
+    pub fn draw_win_screen(&self, title: &str, prompt: &str) {
         let bg_color1 = get_rainbow_color(self.phase);
         let bg_color2 = get_rainbow_color((self.phase + 120.0) % 360.0);
 
@@ -197,7 +840,7 @@ impl Game {
             draw_line(0.0, y as f32, SCREEN_WIDTH, y as f32, 2.0, color);
         }
 
-        let win_text = if left_won { "LEFT PLAYER WINS!" } else { "RIGHT PLAYER WINS!" };
+        let win_text = title;
         let win_size = 50.0;
         let win_hue = self.phase;
 
@@ -208,7 +851,7 @@ impl Game {
             draw_text_ex(
                 win_text,
                 SCREEN_WIDTH / 2.0 - measure_text(win_text, None, win_size as u16, 1.0).width / 2.0 + glow_size,
-                SCREEN_HEIGHT / 2.0 - 50.0 + glow_size,
+                110.0 + glow_size,
                 TextParams {
                     font: None,
                     font_size: win_size as u16,
@@ -221,7 +864,7 @@ impl Game {
         draw_text_ex(
             win_text,
             SCREEN_WIDTH / 2.0 - measure_text(win_text, None, win_size as u16, 1.0).width / 2.0,
-            SCREEN_HEIGHT / 2.0 - 50.0,
+            110.0,
             TextParams {
                 font: None,
                 font_size: win_size as u16,
@@ -230,12 +873,29 @@ impl Game {
             },
         );
 
-        let press_text = "Press ENTER/SPACE to return to menu";
+        draw_rectangle(30.0, 140.0, SCREEN_WIDTH - 60.0, 250.0, Color::new(0.0, 0.0, 0.0, 0.55));
+        let stats_size = 20.0;
+        for (i, line) in self.stats.lines().iter().enumerate() {
+            draw_text_ex(
+                line,
+                50.0,
+                175.0 + i as f32 * 26.0,
+                TextParams {
+                    font: None,
+                    font_size: stats_size as u16,
+                    color: get_rainbow_color((self.phase + i as f32 * 30.0) % 360.0),
+                    ..Default::default()
+                },
+            );
+        }
+        self.stats.draw_graph(Rect::new(460.0, 175.0, 220.0, 190.0), self.phase);
+
+        let press_text = prompt;
         let press_size = 25.0;
         draw_text_ex(
             press_text,
             SCREEN_WIDTH / 2.0 - measure_text(press_text, None, press_size as u16, 1.0).width / 2.0,
-            SCREEN_HEIGHT / 2.0 + 50.0,
+            SCREEN_HEIGHT - 60.0,
             TextParams {
                 font: None,
                 font_size: press_size as u16,
@@ -282,7 +942,7 @@ impl Game {
             },
         );
 
-        let instruction_text = "Press P or ESC to resume";
+        let instruction_text = "Press P or ESC to resume, Q to quit";
         let instruction_size = 28.0;
         let instruction_hue = (self.phase + 180.0) % 360.0;
         
@@ -299,8 +959,20 @@ impl Game {
         );
 
         let score_info_size = 20.0;
-        let left_score_text = format!("Left: {}", self.left_score);
-        let right_score_text = format!("Right: {}", self.right_score);
+        let mut left_score_text = format!("Left: {}", self.score.points[0]);
+        let mut right_score_text = format!("Right: {}", self.score.points[1]);
+        if self.is_four_player() {
+            let lives = self.lives;
+            left_score_text = format!("Left: {}  Top: {}", lives[0], lives[2]);
+            right_score_text = format!("Right: {}  Bottom: {}", lives[1], lives[3]);
+        } else if self.score.rules.best_of_games > 1 {
+            left_score_text.push_str(&format!("  G{}", self.score.games[0]));
+            right_score_text.push_str(&format!("  G{}", self.score.games[1]));
+        }
+        if self.score.rules.best_of_sets > 1 && !self.is_four_player() {
+            left_score_text.push_str(&format!(" S{}", self.score.sets[0]));
+            right_score_text.push_str(&format!(" S{}", self.score.sets[1]));
+        }
         
         draw_text_ex(
             &left_score_text,
@@ -316,7 +988,7 @@ impl Game {
 
         draw_text_ex(
             &right_score_text,
-            SCREEN_WIDTH - 150.0,
+            SCREEN_WIDTH - 50.0 - measure_text(&right_score_text, None, score_info_size as u16, 1.0).width,
             SCREEN_HEIGHT / 2.0 + 80.0,
             TextParams {
                 font: None,
@@ -326,7 +998,7 @@ impl Game {
             },
         );
 
-        let win_score_text = format!("First to {} wins", crate::consts::WIN_SCORE);
+        let win_score_text = self.score.rules.describe();
         draw_text_ex(
             &win_score_text,
             SCREEN_WIDTH / 2.0 - measure_text(&win_score_text, None, score_info_size as u16, 1.0).width / 2.0,
@@ -344,7 +1016,35 @@ impl Game {
 #[derive(PartialEq)]
 pub enum GameResult {
     Continue,
-    LeftWins,
-    RightWins,
+    Winner(Side),
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_without_keys_keep_their_seat_bindings() {
+        let settings = Settings::default();
+        let mut game = Game::new([true, true, false, false], MatchRules::classic(), Arena::classic());
+        game.apply_settings(&settings);
+        game.assign_profile(0, 0, &Profile::new("ONE".to_string(), 0.0));
+        game.assign_profile(1, 1, &Profile::new("TWO".to_string(), 60.0));
+
+        assert_eq!(game.paddles[0].bindings, settings.bindings[0]);
+        assert_eq!(game.paddles[1].bindings, settings.bindings[1]);
+        assert_ne!(game.paddles[0].bindings, game.paddles[1].bindings);
+    }
+
+    #[test]
+    fn a_profile_with_its_own_keys_overrides_the_seat() {
+        let mut game = Game::new([true, true, false, false], MatchRules::classic(), Arena::classic());
+        game.apply_settings(&Settings::default());
+        let mut profile = Profile::new("ONE".to_string(), 0.0);
+        profile.bindings = Some(KeyBindings::for_seat(2));
+        game.assign_profile(1, 0, &profile);
+
+        assert_eq!(game.paddles[1].bindings, KeyBindings::for_seat(2));
+    }
+}
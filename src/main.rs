@@ -5,36 +5,159 @@ mod ball;
 mod menu;
 mod game;
 mod audio;
+mod rules;
+mod powerups;
+mod arena;
+mod editor;
+mod controls;
+mod bricks;
+mod practice;
+mod survival;
+mod storage;
+mod ai;
+mod campaign;
+mod stats;
+mod profiles;
+mod achievements;
+mod events;
+mod synth;
+mod music;
+mod settings;
 
 use macroquad::prelude::*;
 use menu::{Menu, MenuChoice};
 use game::Game;
+use editor::{Editor, EditorAction};
+use practice::{PracticeResult, PracticeSession};
+use survival::{SurvivalResult, SurvivalSession};
+use rules::Side;
+use campaign::{ladder, CampaignProgress, Opponent};
+use audio::{AudioSystem, Sfx};
+use settings::Settings;
 
-#[macroquad::main("Colorful Pong")]
-async fn main() {
+fn campaign_game(menu: &Menu, opponent: &Opponent) -> Game {
+    let mut game = Game::new([true, false, false, false], opponent.rules, menu.arena_named(opponent.arena));
+    game.apply_settings(menu.settings());
+    menu.apply_profiles(&mut game);
+    game
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--render-music") {
+        audio::render_music();
+        return;
+    }
+    macroquad::Window::new("Colorful Pong", run());
+}
+
+async fn run() {
     let mut current_state = GameState::Menu;
-    let mut menu = Menu::new();
+    let settings = Settings::load();
+    set_fullscreen(settings.fullscreen);
+    let mut menu = Menu::new(settings);
     let mut game: Option<Game> = None;
+    let mut editor: Option<Editor> = None;
+    let mut practice: Option<PracticeSession> = None;
+    let mut survival: Option<SurvivalSession> = None;
+    let ladder = ladder();
+    let mut campaign_stage: Option<usize> = None;
+    let mut audio = AudioSystem::load().await;
 
     loop {
         let dt = get_frame_time();
+        effects::set_screen_camera();
+        audio.apply_settings(menu.audio_settings());
+        match current_state {
+            GameState::Menu | GameState::Editor => audio.update_menu(dt),
+            _ => audio.update_game(dt),
+        }
 
+        let mut ball = None;
         match current_state {
             GameState::Menu => {
                 clear_background(Color::new(0.0, 0.0, 0.0, 1.0));
-                match menu.update(dt) {
-                    MenuChoice::OnePlayer => {
-                        game = Some(Game::new(false));
+                let choice = menu.update(dt);
+                if menu.clicked() {
+                    audio.play(Sfx::Select, 0, None);
+                }
+                match choice {
+                    MenuChoice::Play(humans) => {
+                        let mut new_game = Game::new(humans, menu.rules(), menu.arena());
+                        new_game.apply_settings(menu.settings());
+                        menu.apply_profiles(&mut new_game);
+                        game = Some(new_game);
                         current_state = GameState::Playing;
                     }
-                    MenuChoice::TwoPlayers => {
-                        game = Some(Game::new(true));
+                    MenuChoice::Practice => {
+                        practice = Some(PracticeSession::new(menu.rules(), menu.settings()));
+                        current_state = GameState::Practice;
+                    }
+                    MenuChoice::Survival => {
+                        survival = Some(SurvivalSession::new(menu.rules(), menu.settings()));
+                        current_state = GameState::Survival;
+                    }
+                    MenuChoice::Campaign(stage) => {
+                        game = Some(campaign_game(&menu, &ladder[stage]));
+                        campaign_stage = Some(stage);
                         current_state = GameState::Playing;
                     }
+                    MenuChoice::Editor => {
+                        editor = Some(Editor::new(menu.arena()));
+                        current_state = GameState::Editor;
+                    }
                     MenuChoice::None => {}
                 }
                 menu.draw();
             }
+            GameState::Editor => {
+                if let Some(ref mut editor_instance) = editor {
+                    clear_background(Color::new(0.0, 0.0, 0.0, 1.0));
+                    match editor_instance.update(dt) {
+                        EditorAction::TestPlay(arena) => {
+                            let mut test_game = Game::new([true, false, false, false], menu.rules(), arena);
+                            test_game.apply_settings(menu.settings());
+                            menu.apply_profiles(&mut test_game);
+                            game = Some(test_game);
+                            current_state = GameState::Playing;
+                        }
+                        EditorAction::Exit => {
+                            menu.reload_arenas();
+                            editor = None;
+                            current_state = GameState::Menu;
+                        }
+                        EditorAction::None => {}
+                    }
+                    if let Some(ref editor_instance) = editor {
+                        editor_instance.draw();
+                    }
+                }
+            }
+            GameState::Practice => {
+                if let Some(ref mut session) = practice {
+                    clear_background(Color::new(0.0, 0.0, 0.0, 1.0));
+                    let result = session.update(dt);
+                    audio.play_events(session.events());
+                    ball = session.ball_position();
+                    session.draw();
+                    if let PracticeResult::Exit = result {
+                        practice = None;
+                        current_state = GameState::Menu;
+                    }
+                }
+            }
+            GameState::Survival => {
+                if let Some(ref mut session) = survival {
+                    clear_background(Color::new(0.0, 0.0, 0.0, 1.0));
+                    let result = session.update(dt);
+                    audio.play_events(session.events());
+                    ball = session.ball_position();
+                    session.draw();
+                    if let SurvivalResult::Exit = result {
+                        survival = None;
+                        current_state = GameState::Menu;
+                    }
+                }
+            }
             GameState::Playing => {
                 if let Some(ref mut game_instance) = game {
                     if is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Escape) {
@@ -42,15 +165,18 @@ async fn main() {
                     } else {
                         clear_background(Color::new(0.0, 0.0, 0.0, 1.0));
                         let result = game_instance.update(dt);
+                        audio.play_events(game_instance.events());
+                        ball = game_instance.ball_position();
                         game_instance.draw();
                         
                         match result {
                             game::GameResult::Continue => {}
-                            game::GameResult::LeftWins => {
-                                current_state = GameState::GameOver(true);
-                            }
-                            game::GameResult::RightWins => {
-                                current_state = GameState::GameOver(false);
+                            game::GameResult::Winner(side) => {
+                                menu.record_match(game_instance, side);
+                                if let (Some(stage), Side::Left) = (campaign_stage, side) {
+                                    CampaignProgress::load().record_win(stage);
+                                }
+                                current_state = GameState::GameOver(side);
                             }
                         }
                     }
@@ -64,23 +190,55 @@ async fn main() {
                     
                     if is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Escape) {
                         current_state = GameState::Playing;
+                    } else if is_key_pressed(KeyCode::Q) {
+                        current_state = if editor.is_some() { GameState::Editor } else { GameState::Menu };
+                        menu.record_achievements(game_instance);
+                        if campaign_stage.take().is_some() {
+                            menu.show_campaign();
+                        }
+                        game = None;
                     }
                 }
             }
-            GameState::GameOver(left_won) => {
+            GameState::GameOver(winner) => {
                 if let Some(ref mut game_instance) = game {
                     clear_background(Color::new(0.0, 0.0, 0.0, 1.0));
                     game_instance.draw();
-                    game_instance.draw_win_screen(left_won);
-                    
-                    if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
-                        current_state = GameState::Menu;
+
+                    let next_stage = match campaign_stage {
+                        Some(stage) if winner == Side::Left => (stage + 1 < ladder.len()).then_some(stage + 1),
+                        stage => stage,
+                    };
+                    let (title, prompt) = match (campaign_stage, next_stage) {
+                        (None, _) => (game_instance.winner_title(winner), "Press ENTER/SPACE to return to menu".to_string()),
+                        (Some(stage), Some(next)) if next > stage => (
+                            format!("YOU BEAT {}!", ladder[stage].name),
+                            format!("Press ENTER to face {}, ESC for menu", ladder[next].name),
+                        ),
+                        (Some(_), None) => ("CAMPAIGN COMPLETE!".to_string(), "Press ENTER/SPACE to return to menu".to_string()),
+                        (Some(stage), Some(_)) => (
+                            format!("{} WINS!", ladder[stage].name),
+                            "Press ENTER to try again, ESC for menu".to_string(),
+                        ),
+                    };
+                    game_instance.draw_win_screen(&title, &prompt);
+
+                    let confirm = is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space);
+                    if let (Some(next), true) = (next_stage, confirm) {
+                        game = Some(campaign_game(&menu, &ladder[next]));
+                        campaign_stage = Some(next);
+                        current_state = GameState::Playing;
+                    } else if confirm || (campaign_stage.is_some() && is_key_pressed(KeyCode::Escape)) {
+                        current_state = if editor.is_some() { GameState::Editor } else { GameState::Menu };
+                        if campaign_stage.take().is_some() {
+                            menu.show_campaign();
+                        }
                         game = None;
-                        menu = Menu::new();
                     }
                 }
             }
         }
+        audio.update_ball_tone(dt, ball);
 
         next_frame().await;
     }
@@ -88,8 +246,11 @@ async fn main() {
 
 enum GameState {
     Menu,
+    Editor,
+    Practice,
+    Survival,
     Playing,
     Paused,
-    GameOver(bool),
+    GameOver(Side),
 }
 
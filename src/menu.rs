@@ -1,11 +1,112 @@
 use macroquad::prelude::*;
 use crate::consts::*;
 use crate::effects::*;
+use crate::rules::{GameMode, MatchRules, PointEnd};
+use crate::powerups::PowerUpKind;
+use crate::arena::Arena;
+use crate::controls::{key_name, KeyBindings, BINDABLE_KEYS, BINDING_NAMES};
+use crate::practice::Drill;
+use crate::survival::HighScores;
+use crate::campaign::{ladder, CampaignProgress, Opponent};
+use crate::profiles::Profiles;
+use crate::achievements::{definitions, Achievement};
+use crate::game::Game;
+use crate::rules::Side;
+use crate::audio::AudioSettings;
+use crate::ai::Difficulty;
+use crate::settings::Settings;
 
 pub enum MenuChoice {
     None,
+    Play([bool; 4]),
+    Practice,
+    Survival,
+    Campaign(usize),
+    Editor,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MenuScreen {
+    Main,
+    PowerUps,
+    Seats,
+    HighScores,
+    Campaign,
+    Profiles,
+    EditProfile,
+    Achievements,
+    Settings,
+    Sound,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum KeyTarget {
+    Profile,
+    Seat(usize),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Capture {
+    Name,
+    Keys(KeyTarget, usize, KeyBindings),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MenuItem {
     OnePlayer,
     TwoPlayers,
+    Start,
+    Mode,
+    Seats,
+    Lives,
+    PaddleZone,
+    Drill,
+    MachineSpeed,
+    MachineRate,
+    MachineShots,
+    HighScores,
+    Campaign,
+    Opponent(usize),
+    Player(usize),
+    Profiles,
+    Profile(usize),
+    NewProfile,
+    ProfileName,
+    ProfileColor,
+    ProfileKeys,
+    DeleteProfile,
+    Achievements,
+    Achievement(usize),
+    Settings,
+    Sound,
+    SfxVolume,
+    MusicVolume,
+    Mute,
+    BallTone,
+    ToneVolume,
+    VisualEffects,
+    ScreenShake,
+    Difficulty,
+    WinScore,
+    Fullscreen,
+    SeatKeys(usize),
+    Seat(usize),
+    PointEnd,
+    Arena,
+    Rules,
+    Serve,
+    Spin,
+    PowerUps,
+    Editor,
+    PowerUp(PowerUpKind),
+    Back,
+}
+
+const MAX_VISIBLE_ITEMS: usize = 7;
+
+fn cycle<T: PartialEq + Copy>(options: &[T], current: T, step: i32) -> T {
+    let index = options.iter().position(|option| *option == current).unwrap_or(0) as i32;
+    options[(index + step).rem_euclid(options.len() as i32) as usize]
 }
 
 pub struct Star {
@@ -18,12 +119,32 @@ pub struct Star {
 pub struct Menu {
     phase: f32,
     selected_option: usize,
+    screen: MenuScreen,
+    main_selection: usize,
     stars: Vec<Star>,
     floating_particles: Vec<(Vec2, Vec2, f32)>,
+    rule_presets: Vec<MatchRules>,
+    selected_rules: usize,
+    rules: MatchRules,
+    arenas: Vec<Arena>,
+    selected_arena: usize,
+    seats: [bool; 4],
+    high_scores: HighScores,
+    ladder: Vec<Opponent>,
+    campaign: CampaignProgress,
+    profiles: Profiles,
+    players: [Option<usize>; 2],
+    editing: usize,
+    capture: Option<Capture>,
+    name_input: String,
+    achievements: Vec<Achievement>,
+    settings: Settings,
+    clicked: bool,
 }
 
 impl Menu {
-    pub fn new() -> Self {
+    pub fn new(settings: Settings) -> Self {
+        let rules = settings.match_rules();
         let mut stars = Vec::new();
         for _ in 0..30 {
             stars.push(Star {
@@ -52,11 +173,570 @@ impl Menu {
             ));
         }
 
+        let mut rule_presets = MatchRules::presets();
+        rule_presets[0].target_score = settings.win_score;
+        let same_format = |preset: &MatchRules| rules.with_format(preset) == rules;
+        let selected_rules = match rule_presets.iter().position(same_format) {
+            Some(index) => index,
+            None => {
+                rule_presets.push(rules);
+                rule_presets.len() - 1
+            }
+        };
+
         Self {
             phase: 0.0,
             selected_option: 0,
+            screen: MenuScreen::Main,
+            main_selection: 0,
             stars,
             floating_particles,
+            rule_presets,
+            selected_rules,
+            rules,
+            arenas: Arena::load_all(),
+            selected_arena: 0,
+            seats: [true, false, false, false],
+            high_scores: HighScores::default(),
+            ladder: ladder(),
+            campaign: CampaignProgress::load(),
+            profiles: Profiles::load(),
+            players: [None, None],
+            editing: 0,
+            capture: None,
+            name_input: String::new(),
+            achievements: definitions(),
+            settings,
+            clicked: false,
+        }
+    }
+
+    pub fn reload_arenas(&mut self) {
+        let current = self.arena().name;
+        self.arenas = Arena::load_all();
+        self.selected_arena = self.arenas.iter().position(|arena| arena.name == current).unwrap_or(0);
+    }
+
+    pub fn arena(&self) -> Arena {
+        self.arenas.get(self.selected_arena).cloned().unwrap_or_else(Arena::classic)
+    }
+
+    pub fn show_campaign(&mut self) {
+        self.open_screen(MenuScreen::Campaign);
+    }
+
+    pub fn arena_named(&self, name: &str) -> Arena {
+        self.arenas.iter().find(|arena| arena.name == name).cloned().unwrap_or_else(Arena::classic)
+    }
+
+    pub fn rules(&self) -> MatchRules {
+        self.rules
+    }
+
+    pub fn audio_settings(&self) -> AudioSettings {
+        self.settings.audio
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn clicked(&self) -> bool {
+        self.clicked
+    }
+
+    fn items(&self) -> Vec<MenuItem> {
+        match self.screen {
+            MenuScreen::Main if self.rules.mode == GameMode::Practice => vec![
+                MenuItem::Start,
+                MenuItem::Mode,
+                MenuItem::Drill,
+                MenuItem::MachineSpeed,
+                MenuItem::MachineRate,
+                MenuItem::MachineShots,
+                MenuItem::PaddleZone,
+                MenuItem::Spin,
+                MenuItem::Profiles,
+                MenuItem::Campaign,
+                MenuItem::Settings,
+                MenuItem::Editor,
+            ],
+            MenuScreen::Main if self.rules.mode == GameMode::Survival => vec![
+                MenuItem::Start,
+                MenuItem::Mode,
+                MenuItem::HighScores,
+                MenuItem::PaddleZone,
+                MenuItem::Spin,
+                MenuItem::Profiles,
+                MenuItem::Campaign,
+                MenuItem::Settings,
+                MenuItem::Editor,
+            ],
+            MenuScreen::HighScores => vec![MenuItem::Back],
+            MenuScreen::Settings => {
+                let mut items = vec![
+                    MenuItem::Sound,
+                    MenuItem::VisualEffects,
+                    MenuItem::ScreenShake,
+                    MenuItem::Difficulty,
+                    MenuItem::WinScore,
+                    MenuItem::Fullscreen,
+                ];
+                items.extend((0..self.settings.bindings.len()).map(MenuItem::SeatKeys));
+                items.push(MenuItem::Back);
+                items
+            }
+            MenuScreen::Sound => vec![
+                MenuItem::SfxVolume,
+                MenuItem::MusicVolume,
+                MenuItem::BallTone,
+                MenuItem::ToneVolume,
+                MenuItem::Mute,
+                MenuItem::Back,
+            ],
+            MenuScreen::Campaign => {
+                let mut items: Vec<MenuItem> = (0..self.ladder.len()).map(MenuItem::Opponent).collect();
+                items.push(MenuItem::Back);
+                items
+            }
+            MenuScreen::Profiles => {
+                let mut items: Vec<MenuItem> = (0..self.profiles.entries.len()).map(MenuItem::Profile).collect();
+                items.extend([MenuItem::NewProfile, MenuItem::Back]);
+                items
+            }
+            MenuScreen::EditProfile => vec![
+                MenuItem::ProfileName,
+                MenuItem::ProfileColor,
+                MenuItem::ProfileKeys,
+                MenuItem::Achievements,
+                MenuItem::DeleteProfile,
+                MenuItem::Back,
+            ],
+            MenuScreen::Achievements => {
+                let mut items: Vec<MenuItem> = (0..self.achievements.len()).map(MenuItem::Achievement).collect();
+                items.push(MenuItem::Back);
+                items
+            }
+            MenuScreen::Main => {
+                let mut items = if self.rules.mode.has_seat_setup() {
+                    vec![MenuItem::Start, MenuItem::Mode, MenuItem::Seats]
+                } else {
+                    vec![MenuItem::OnePlayer, MenuItem::TwoPlayers, MenuItem::Mode]
+                };
+                items.extend([MenuItem::Player(0), MenuItem::Player(1)]);
+                if self.rules.mode == GameMode::FourPlayer {
+                    items.push(MenuItem::Lives);
+                }
+                if self.rules.mode == GameMode::MultiBall {
+                    items.push(MenuItem::PointEnd);
+                }
+                items.push(MenuItem::Arena);
+                if self.rules.mode != GameMode::FourPlayer {
+                    items.push(MenuItem::Rules);
+                }
+                items.extend([
+                    MenuItem::PaddleZone,
+                    MenuItem::Serve,
+                    MenuItem::Spin,
+                    MenuItem::PowerUps,
+                    MenuItem::Profiles,
+                    MenuItem::Campaign,
+                    MenuItem::Settings,
+                    MenuItem::Editor,
+                ]);
+                items
+            }
+            MenuScreen::PowerUps => {
+                let mut items: Vec<MenuItem> = PowerUpKind::ALL.iter().map(|kind| MenuItem::PowerUp(*kind)).collect();
+                items.push(MenuItem::Back);
+                items
+            }
+            MenuScreen::Seats => {
+                let mut items: Vec<MenuItem> = (0..self.rules.mode.seats().len()).map(MenuItem::Seat).collect();
+                items.push(MenuItem::Back);
+                items
+            }
+        }
+    }
+
+    fn open_screen(&mut self, screen: MenuScreen) {
+        if self.screen == MenuScreen::Main {
+            self.main_selection = self.selected_option;
+        }
+        if screen == MenuScreen::HighScores {
+            self.high_scores = HighScores::load();
+        }
+        self.screen = screen;
+        self.selected_option = if screen == MenuScreen::Main { self.main_selection } else { 0 };
+        if screen == MenuScreen::Campaign {
+            self.campaign = CampaignProgress::load();
+            self.selected_option = self.campaign.cleared.min(self.ladder.len() - 1);
+        }
+        if screen == MenuScreen::Profiles {
+            self.selected_option = self.editing.min(self.profiles.entries.len());
+        }
+    }
+
+    fn parent_screen(&self) -> MenuScreen {
+        match self.screen {
+            MenuScreen::EditProfile => MenuScreen::Profiles,
+            MenuScreen::Achievements => MenuScreen::EditProfile,
+            MenuScreen::Sound => MenuScreen::Settings,
+            _ => MenuScreen::Main,
+        }
+    }
+
+    fn save_profiles(&self) {
+        if let Err(err) = self.profiles.save() {
+            eprintln!("Could not save profiles: {}", err);
+        }
+    }
+
+    fn save_settings(&self) {
+        if let Err(err) = self.settings.save() {
+            eprintln!("Could not save settings: {}", err);
+        }
+    }
+
+    fn seat_bindings(&self, seat: usize) -> KeyBindings {
+        self.players
+            .get(seat)
+            .and_then(|player| player.and_then(|index| self.profiles.entries.get(index)))
+            .and_then(|profile| profile.bindings)
+            .unwrap_or(self.settings.bindings[seat])
+    }
+
+    fn delete_profile(&mut self, index: usize) {
+        self.profiles.remove(index);
+        for player in &mut self.players {
+            *player = match *player {
+                Some(selected) if selected == index => None,
+                Some(selected) if selected > index => Some(selected - 1),
+                other => other,
+            };
+        }
+        self.editing = index.saturating_sub(1);
+        self.save_profiles();
+    }
+
+    pub fn apply_profiles(&self, game: &mut Game) {
+        for (seat, player) in self.players.iter().enumerate() {
+            let Some(index) = *player else {
+                continue;
+            };
+            if let Some(profile) = self.profiles.entries.get(index) {
+                game.assign_profile(seat, index, profile);
+            }
+        }
+    }
+
+    pub fn record_match(&mut self, game: &Game, winner: Side) {
+        for seated in game.seated_profiles() {
+            if let Some(profile) = self.profiles.entries.get_mut(seated.index) {
+                profile.record_match(game.mode(), seated.side == winner, game.stats());
+            }
+        }
+        self.record_achievements(game);
+    }
+
+    pub fn record_achievements(&mut self, game: &Game) {
+        for (index, id) in game.unlocked_achievements() {
+            if let Some(profile) = self.profiles.entries.get_mut(*index) {
+                if !profile.achievements.contains(id) {
+                    profile.achievements.push(id.clone());
+                }
+            }
+        }
+        if !game.seated_profiles().is_empty() {
+            self.save_profiles();
+        }
+    }
+
+    fn has_achievement(&self, achievement: usize) -> bool {
+        self.profiles.entries[self.editing].achievements.contains(&self.achievements[achievement].id)
+    }
+
+    fn update_capture(&mut self, capture: Capture) {
+        if is_key_pressed(KeyCode::Escape) {
+            self.capture = None;
+            return;
+        }
+        match capture {
+            Capture::Name => {
+                while let Some(c) = get_char_pressed() {
+                    if (c.is_ascii_alphanumeric() || c == ' ') && self.name_input.len() < 12 {
+                        self.name_input.push(c.to_ascii_uppercase());
+                    }
+                }
+                if is_key_pressed(KeyCode::Backspace) {
+                    self.name_input.pop();
+                }
+                if is_key_pressed(KeyCode::Enter) {
+                    let name = self.name_input.trim();
+                    if !name.is_empty() {
+                        self.profiles.entries[self.editing].name = name.to_string();
+                        self.save_profiles();
+                    }
+                    self.capture = None;
+                }
+            }
+            Capture::Keys(target, step, mut bindings) => {
+                let Some(key) = get_last_key_pressed().filter(|key| BINDABLE_KEYS.contains(key)) else {
+                    return;
+                };
+                bindings.set(step, key);
+                if step + 1 < BINDING_NAMES.len() {
+                    self.capture = Some(Capture::Keys(target, step + 1, bindings));
+                    return;
+                }
+                match target {
+                    KeyTarget::Profile => {
+                        self.profiles.entries[self.editing].bindings = Some(bindings);
+                        self.save_profiles();
+                    }
+                    KeyTarget::Seat(seat) => {
+                        self.settings.bindings[seat] = bindings;
+                        self.save_settings();
+                    }
+                }
+                self.capture = None;
+            }
+        }
+    }
+
+    fn item_label(&self, item: MenuItem) -> String {
+        match item {
+            MenuItem::OnePlayer => "1 PLAYER".to_string(),
+            MenuItem::TwoPlayers => "2 PLAYERS".to_string(),
+            MenuItem::Start => "START".to_string(),
+            MenuItem::HighScores => "HIGH SCORES".to_string(),
+            MenuItem::Campaign => format!("CAMPAIGN: {}/{}", self.campaign.cleared.min(self.ladder.len()), self.ladder.len()),
+            MenuItem::Player(seat) => {
+                let name = self.players[seat]
+                    .and_then(|index| self.profiles.entries.get(index))
+                    .map(|profile| profile.name.as_str())
+                    .unwrap_or("GUEST");
+                format!("PLAYER {}: < {} >", seat + 1, name)
+            }
+            MenuItem::Profiles => "PROFILES".to_string(),
+            MenuItem::Settings => "SETTINGS".to_string(),
+            MenuItem::Sound if self.settings.audio.muted => "SOUND: MUTED".to_string(),
+            MenuItem::Sound => "SOUND".to_string(),
+            MenuItem::SfxVolume => format!("EFFECTS VOLUME: < {}% >", (self.settings.audio.sfx_volume * 100.0).round()),
+            MenuItem::MusicVolume => format!("MUSIC VOLUME: < {}% >", (self.settings.audio.music_volume * 100.0).round()),
+            MenuItem::Mute => format!("MUTE: {}", if self.settings.audio.muted { "ON" } else { "OFF" }),
+            MenuItem::BallTone => format!("BALL TONE: {}", if self.settings.audio.ball_tone { "ON" } else { "OFF" }),
+            MenuItem::ToneVolume => {
+                format!("BALL TONE VOLUME: < {}% >", (self.settings.audio.tone_volume * 100.0).round())
+            }
+            MenuItem::VisualEffects => format!("VISUAL EFFECTS: < {}% >", (self.settings.effects.intensity * 100.0).round()),
+            MenuItem::ScreenShake => format!("SCREEN SHAKE: {}", if self.settings.effects.screen_shake { "ON" } else { "OFF" }),
+            MenuItem::Difficulty => format!("AI DIFFICULTY: < {} >", self.settings.difficulty.label()),
+            MenuItem::WinScore => format!("WIN SCORE: < {} >", self.settings.win_score),
+            MenuItem::Fullscreen => format!("FULLSCREEN: {}", if self.settings.fullscreen { "ON" } else { "OFF" }),
+            MenuItem::SeatKeys(seat) => match self.capture {
+                Some(Capture::Keys(KeyTarget::Seat(target), step, _)) if target == seat => {
+                    format!("PRESS KEY FOR {}", BINDING_NAMES[step])
+                }
+                _ => {
+                    let bindings = self.settings.bindings[seat];
+                    format!("SEAT {} KEYS: {}  SERVE {}", seat + 1, bindings.movement_label(), key_name(bindings.launch))
+                }
+            },
+            MenuItem::Profile(index) => {
+                let profile = &self.profiles.entries[index];
+                format!("{} - {} WINS", profile.name, profile.stats.total_wins())
+            }
+            MenuItem::NewProfile => "NEW PROFILE".to_string(),
+            MenuItem::ProfileName => match self.capture {
+                Some(Capture::Name) => format!("NAME: {}_", self.name_input),
+                _ => format!("NAME: {}", self.profiles.entries[self.editing].name),
+            },
+            MenuItem::ProfileColor => format!("COLOR: < {} >", self.profiles.entries[self.editing].color_name()),
+            MenuItem::ProfileKeys => match self.capture {
+                Some(Capture::Keys(KeyTarget::Profile, step, _)) => format!("PRESS KEY FOR {}", BINDING_NAMES[step]),
+                _ => match self.profiles.entries[self.editing].bindings {
+                    Some(bindings) => format!("KEYS: {}  SERVE {}", bindings.movement_label(), key_name(bindings.launch)),
+                    None => "KEYS: SEAT DEFAULT".to_string(),
+                },
+            },
+            MenuItem::DeleteProfile => "DELETE PROFILE".to_string(),
+            MenuItem::Achievements => {
+                let unlocked = (0..self.achievements.len()).filter(|i| self.has_achievement(*i)).count();
+                format!("ACHIEVEMENTS: {}/{}", unlocked, self.achievements.len())
+            }
+            MenuItem::Achievement(i) => {
+                let status = if self.has_achievement(i) { "UNLOCKED" } else { "LOCKED" };
+                format!("{} - {}", self.achievements[i].name, status)
+            }
+            MenuItem::Opponent(stage) => {
+                if !self.campaign.is_unlocked(stage) {
+                    format!("{}. ??? - LOCKED", stage + 1)
+                } else if stage < self.campaign.cleared {
+                    format!("{}. {} - CLEARED", stage + 1, self.ladder[stage].name)
+                } else {
+                    format!("{}. {}", stage + 1, self.ladder[stage].name)
+                }
+            }
+            MenuItem::Drill => format!("DRILL: < {} >", self.rules.machine.drill.label()),
+            MenuItem::MachineSpeed => format!("MACHINE SPEED: < {} >", self.rules.machine.speed),
+            MenuItem::MachineRate => format!("BALL EVERY: < {:.1}s >", self.rules.machine.interval),
+            MenuItem::MachineShots => format!("SHOTS: < {} >", self.rules.machine.shots),
+            MenuItem::Seats => {
+                let seat_count = self.rules.mode.seats().len();
+                format!("SEATS: {} HUMAN", self.seats.iter().take(seat_count).filter(|human| **human).count())
+            }
+            MenuItem::Lives => format!("LIVES: < {} >", self.rules.lives),
+            MenuItem::PaddleZone => {
+                if self.rules.paddle_zone > 0.0 {
+                    format!("PADDLE ZONE: < {} >", self.rules.paddle_zone)
+                } else {
+                    "PADDLE ZONE: < OFF >".to_string()
+                }
+            }
+            MenuItem::Seat(seat) => {
+                let label = self.rules.mode.seats()[seat].label();
+                if self.seats[seat] {
+                    format!("{}: HUMAN ({})", label, self.seat_bindings(seat).movement_label())
+                } else {
+                    format!("{}: AI", label)
+                }
+            }
+            MenuItem::Mode => format!("MODE: {}", self.rules.mode.label()),
+            MenuItem::PointEnd => format!("POINT: {}", self.rules.point_end.label()),
+            MenuItem::Arena => format!("ARENA: < {} >", self.arena().name.to_uppercase()),
+            MenuItem::Rules => format!("< {} >", self.rules.short_label()),
+            MenuItem::Serve => format!("SERVE: {}", if self.rules.manual_serve { "LAUNCH KEY" } else { "AUTO" }),
+            MenuItem::Spin => format!("SPIN: {}", if self.rules.spin { "ON" } else { "OFF" }),
+            MenuItem::PowerUps => format!("POWER-UPS: {}/{}", self.rules.power_ups.count(), PowerUpKind::ALL.len()),
+            MenuItem::PowerUp(kind) => format!("{}: {}", kind.label(), if self.rules.power_ups.contains(kind) { "ON" } else { "OFF" }),
+            MenuItem::Editor => "ARENA EDITOR".to_string(),
+            MenuItem::Back => "BACK".to_string(),
+        }
+    }
+
+    fn change_item(&mut self, item: MenuItem, step: i32) {
+        match item {
+            MenuItem::Mode => {
+                let modes = GameMode::ALL;
+                let current = modes.iter().position(|mode| *mode == self.rules.mode).unwrap_or(0) as i32;
+                self.rules.mode = modes[(current + step).rem_euclid(modes.len() as i32) as usize];
+                self.selected_option = self.items().iter().position(|item| *item == MenuItem::Mode).unwrap_or(0);
+            }
+            MenuItem::PointEnd => {
+                self.rules.point_end = match self.rules.point_end {
+                    PointEnd::LastBallOut => PointEnd::EveryBall,
+                    PointEnd::EveryBall => PointEnd::LastBallOut,
+                };
+            }
+            MenuItem::Arena => {
+                let count = self.arenas.len().max(1) as i32;
+                self.selected_arena = (self.selected_arena as i32 + step).rem_euclid(count) as usize;
+            }
+            MenuItem::Rules => {
+                let count = self.rule_presets.len() as i32;
+                self.selected_rules = (self.selected_rules as i32 + step).rem_euclid(count) as usize;
+                self.rules = self.rules.with_format(&self.rule_presets[self.selected_rules]);
+            }
+            MenuItem::Lives => {
+                self.rules.lives = (self.rules.lives as i32 + step).clamp(1, MAX_LIVES as i32) as u32;
+            }
+            MenuItem::PaddleZone => {
+                let count = PADDLE_ZONE_DEPTHS.len() as i32;
+                let current = PADDLE_ZONE_DEPTHS.iter().position(|depth| *depth == self.rules.paddle_zone).unwrap_or(0) as i32;
+                self.rules.paddle_zone = PADDLE_ZONE_DEPTHS[(current + step).rem_euclid(count) as usize];
+            }
+            MenuItem::Drill => self.rules.machine.drill = cycle(&Drill::ALL, self.rules.machine.drill, step),
+            MenuItem::MachineSpeed => self.rules.machine.speed = cycle(&PRACTICE_SPEEDS, self.rules.machine.speed, step),
+            MenuItem::MachineRate => {
+                self.rules.machine.interval = cycle(&PRACTICE_INTERVALS, self.rules.machine.interval, step);
+            }
+            MenuItem::MachineShots => self.rules.machine.shots = cycle(&PRACTICE_SHOT_COUNTS, self.rules.machine.shots, step),
+            MenuItem::Seat(seat) => self.seats[seat] = !self.seats[seat],
+            MenuItem::Player(seat) => {
+                let options: Vec<Option<usize>> =
+                    std::iter::once(None).chain((0..self.profiles.entries.len()).map(Some)).collect();
+                self.players[seat] = cycle(&options, self.players[seat], step);
+            }
+            MenuItem::ProfileColor => {
+                let profile = &mut self.profiles.entries[self.editing];
+                profile.hue = (profile.hue + step as f32 * 30.0).rem_euclid(360.0);
+                self.save_profiles();
+            }
+            MenuItem::SfxVolume => {
+                let audio = &mut self.settings.audio;
+                audio.sfx_volume = (audio.sfx_volume + step as f32 * VOLUME_STEP).clamp(0.0, 1.0);
+                self.save_settings();
+            }
+            MenuItem::MusicVolume => {
+                let audio = &mut self.settings.audio;
+                audio.music_volume = (audio.music_volume + step as f32 * VOLUME_STEP).clamp(0.0, 1.0);
+                self.save_settings();
+            }
+            MenuItem::Mute => {
+                self.settings.audio.muted = !self.settings.audio.muted;
+                self.save_settings();
+            }
+            MenuItem::BallTone => {
+                self.settings.audio.ball_tone = !self.settings.audio.ball_tone;
+                self.save_settings();
+            }
+            MenuItem::ToneVolume => {
+                let audio = &mut self.settings.audio;
+                audio.tone_volume = (audio.tone_volume + step as f32 * VOLUME_STEP).clamp(0.0, 1.0);
+                self.save_settings();
+            }
+            MenuItem::VisualEffects => {
+                let effects = &mut self.settings.effects;
+                effects.intensity = (effects.intensity + step as f32 * EFFECTS_STEP).clamp(0.0, 1.0);
+                self.save_settings();
+            }
+            MenuItem::ScreenShake => {
+                self.settings.effects.screen_shake = !self.settings.effects.screen_shake;
+                self.save_settings();
+            }
+            MenuItem::Difficulty => {
+                self.settings.difficulty = cycle(&Difficulty::ALL, self.settings.difficulty, step);
+                self.rules.ai.difficulty = self.settings.difficulty;
+                self.save_settings();
+            }
+            MenuItem::WinScore => {
+                self.settings.win_score = (self.settings.win_score as i32 + step).clamp(1, MAX_WIN_SCORE as i32) as u32;
+                self.rule_presets[0].target_score = self.settings.win_score;
+                if self.selected_rules == 0 {
+                    self.rules = self.rules.with_format(&self.rule_presets[0]);
+                }
+                self.save_settings();
+            }
+            MenuItem::Fullscreen => {
+                self.settings.fullscreen = !self.settings.fullscreen;
+                set_fullscreen(self.settings.fullscreen);
+                self.save_settings();
+            }
+            MenuItem::Serve => self.rules.manual_serve = !self.rules.manual_serve,
+            MenuItem::Spin => self.rules.spin = !self.rules.spin,
+            MenuItem::PowerUp(kind) => self.rules.power_ups.toggle(kind),
+            MenuItem::OnePlayer
+            | MenuItem::TwoPlayers
+            | MenuItem::Start
+            | MenuItem::Seats
+            | MenuItem::HighScores
+            | MenuItem::Campaign
+            | MenuItem::Opponent(_)
+            | MenuItem::Profiles
+            | MenuItem::Profile(_)
+            | MenuItem::NewProfile
+            | MenuItem::ProfileName
+            | MenuItem::ProfileKeys
+            | MenuItem::DeleteProfile
+            | MenuItem::Achievements
+            | MenuItem::Achievement(_)
+            | MenuItem::Settings
+            | MenuItem::Sound
+            | MenuItem::SeatKeys(_)
+            | MenuItem::PowerUps
+            | MenuItem::Editor
+            | MenuItem::Back => {}
         }
     }
 
@@ -87,23 +767,127 @@ impl Menu {
             if pos.y < 0.0 || pos.y > SCREEN_HEIGHT {
                 vel.y = -vel.y;
             }
-            pos.x = pos.x.max(0.0).min(SCREEN_WIDTH);
-            pos.y = pos.y.max(0.0).min(SCREEN_HEIGHT);
+            pos.x = pos.x.clamp(0.0, SCREEN_WIDTH);
+            pos.y = pos.y.clamp(0.0, SCREEN_HEIGHT);
         }
 
+        self.clicked = false;
+        if let Some(capture) = self.capture {
+            self.update_capture(capture);
+            return MenuChoice::None;
+        }
+
+        let items = self.items();
+        let keys = [
+            KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right, KeyCode::W, KeyCode::S, KeyCode::A, KeyCode::D,
+            KeyCode::Enter, KeyCode::Space, KeyCode::Escape,
+        ];
+        self.clicked = keys.into_iter().any(is_key_pressed);
+
         if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
-            self.selected_option = if self.selected_option == 0 { 1 } else { 0 };
+            self.selected_option = (self.selected_option + items.len() - 1) % items.len();
         }
 
         if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
-            self.selected_option = if self.selected_option == 1 { 0 } else { 1 };
+            self.selected_option = (self.selected_option + 1) % items.len();
+        }
+
+        if is_key_pressed(KeyCode::Escape) && self.screen != MenuScreen::Main {
+            self.open_screen(self.parent_screen());
+            return MenuChoice::None;
+        }
+
+        let item = items[self.selected_option];
+
+        if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A) {
+            self.change_item(item, -1);
+        }
+
+        if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D) {
+            self.change_item(item, 1);
         }
 
         if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
-            return match self.selected_option {
-                0 => MenuChoice::OnePlayer,
-                1 => MenuChoice::TwoPlayers,
-                _ => MenuChoice::None,
+            return match item {
+                MenuItem::OnePlayer => MenuChoice::Play([true, false, false, false]),
+                MenuItem::TwoPlayers => MenuChoice::Play([true, true, false, false]),
+                MenuItem::Start if self.rules.mode == GameMode::Practice => MenuChoice::Practice,
+                MenuItem::Start if self.rules.mode == GameMode::Survival => MenuChoice::Survival,
+                MenuItem::HighScores => {
+                    self.open_screen(MenuScreen::HighScores);
+                    MenuChoice::None
+                }
+                MenuItem::Campaign => {
+                    self.open_screen(MenuScreen::Campaign);
+                    MenuChoice::None
+                }
+                MenuItem::Opponent(stage) if self.campaign.is_unlocked(stage) => MenuChoice::Campaign(stage),
+                MenuItem::Opponent(_) => MenuChoice::None,
+                MenuItem::Start => MenuChoice::Play(self.seats),
+                MenuItem::Editor => MenuChoice::Editor,
+                MenuItem::PowerUps => {
+                    self.open_screen(MenuScreen::PowerUps);
+                    MenuChoice::None
+                }
+                MenuItem::Seats => {
+                    self.open_screen(MenuScreen::Seats);
+                    MenuChoice::None
+                }
+                MenuItem::Profiles => {
+                    self.open_screen(MenuScreen::Profiles);
+                    MenuChoice::None
+                }
+                MenuItem::Settings => {
+                    self.open_screen(MenuScreen::Settings);
+                    MenuChoice::None
+                }
+                MenuItem::Sound => {
+                    self.open_screen(MenuScreen::Sound);
+                    MenuChoice::None
+                }
+                MenuItem::SeatKeys(seat) => {
+                    self.capture = Some(Capture::Keys(KeyTarget::Seat(seat), 0, self.settings.bindings[seat]));
+                    MenuChoice::None
+                }
+                MenuItem::Profile(index) => {
+                    self.editing = index;
+                    self.open_screen(MenuScreen::EditProfile);
+                    MenuChoice::None
+                }
+                MenuItem::NewProfile => {
+                    self.editing = self.profiles.create();
+                    self.save_profiles();
+                    self.open_screen(MenuScreen::EditProfile);
+                    MenuChoice::None
+                }
+                MenuItem::ProfileName => {
+                    while get_char_pressed().is_some() {}
+                    self.name_input = self.profiles.entries[self.editing].name.clone();
+                    self.capture = Some(Capture::Name);
+                    MenuChoice::None
+                }
+                MenuItem::ProfileKeys => {
+                    let bindings = self.profiles.entries[self.editing].bindings.unwrap_or(self.settings.bindings[0]);
+                    self.capture = Some(Capture::Keys(KeyTarget::Profile, 0, bindings));
+                    MenuChoice::None
+                }
+                MenuItem::Achievements => {
+                    self.open_screen(MenuScreen::Achievements);
+                    MenuChoice::None
+                }
+                MenuItem::DeleteProfile => {
+                    self.delete_profile(self.editing);
+                    self.open_screen(MenuScreen::Profiles);
+                    MenuChoice::None
+                }
+                MenuItem::Back => {
+                    self.open_screen(self.parent_screen());
+                    MenuChoice::None
+                }
+                _ => {
+                    self.change_item(item, 1);
+                    MenuChoice::None
+                }
             };
         }
 
@@ -206,17 +990,37 @@ impl Menu {
             },
         );
 
-        let option_box_width = 300.0;
-        let option_box_height = 70.0;
-        let option_size = 40.0;
-        let options = ["1 PLAYER", "2 PLAYERS"];
+        let option_box_width = 400.0;
+        let option_box_height = 36.0;
+        let option_size = 26.0;
+        let items = self.items();
+        let first_visible = self
+            .selected_option
+            .saturating_sub(MAX_VISIBLE_ITEMS - 1)
+            .min(items.len().saturating_sub(MAX_VISIBLE_ITEMS));
+        let options: Vec<String> = items
+            .iter()
+            .skip(first_visible)
+            .take(MAX_VISIBLE_ITEMS)
+            .map(|item| self.item_label(*item))
+            .collect();
+
+        let rows_top = if self.screen == MenuScreen::HighScores {
+            self.high_scores.draw_table(SCREEN_HEIGHT / 2.0 - 100.0, None, self.phase);
+            SCREEN_HEIGHT - 140.0
+        } else {
+            SCREEN_HEIGHT / 2.0 - 80.0
+        };
 
-        for (i, option) in options.iter().enumerate() {
+        for (row, option) in options.iter().enumerate() {
+            let option = option.as_str();
+            let i = first_visible + row;
             let is_selected = i == self.selected_option;
-            let y_pos = SCREEN_HEIGHT / 2.0 + (i as f32 * 80.0);
+            let y_pos = rows_top + (row as f32 * 42.0);
             let option_hue = (self.phase + i as f32 * 60.0) % 360.0;
             let box_x = SCREEN_WIDTH / 2.0 - option_box_width / 2.0;
             let box_y = y_pos - option_box_height / 2.0;
+            let text_y = y_pos + option_size * 0.3;
 
             let box_color = if is_selected {
                 get_rainbow_color(option_hue)
@@ -277,7 +1081,7 @@ impl Menu {
                     draw_text_ex(
                         option,
                         SCREEN_WIDTH / 2.0 - measure_text(option, None, option_size as u16, 1.0).width / 2.0 + glow_size,
-                        y_pos + glow_size,
+                        text_y + glow_size,
                         TextParams {
                             font: None,
                             font_size: option_size as u16,
@@ -297,7 +1101,7 @@ impl Menu {
             draw_text_ex(
                 option,
                 SCREEN_WIDTH / 2.0 - measure_text(option, None, option_size as u16, 1.0).width / 2.0,
-                y_pos,
+                text_y,
                 TextParams {
                     font: None,
                     font_size: option_size as u16,
@@ -312,7 +1116,7 @@ impl Menu {
                 draw_text_ex(
                     ">",
                     arrow_x,
-                    y_pos,
+                    text_y,
                     TextParams {
                         font: None,
                         font_size: option_size as u16,
@@ -354,80 +1158,80 @@ impl Menu {
             },
         );
 
-        let left_hue = (self.phase + 30.0) % 360.0;
-        draw_text_ex(
-            "LEFT: W / S",
-            SCREEN_WIDTH - 200.0,
-            controls_y_start + 25.0,
-            TextParams {
-                font: None,
-                font_size: controls_size as u16,
-                color: get_rainbow_color(left_hue),
-                ..Default::default()
-            },
-        );
-
-        let right_text_x = SCREEN_WIDTH - 200.0;
-        let right_text_y = controls_y_start + 50.0;
-        let right_hue = (self.phase + 60.0) % 360.0;
-        draw_text_ex(
-            "RIGHT:",
-            right_text_x,
-            right_text_y,
-            TextParams {
-                font: None,
-                font_size: controls_size as u16,
-                color: get_rainbow_color(right_hue),
-                ..Default::default()
-            },
-        );
-
-        let arrow_start_x = right_text_x + measure_text("RIGHT: ", None, controls_size as u16, 1.0).width;
-        let arrow_y = right_text_y;
-        let arrow_size = 8.0;
-        
-        let up_arrow_hue = (self.phase + 90.0) % 360.0;
-        let up_arrow_color = get_rainbow_color(up_arrow_hue);
-        
-        draw_line(arrow_start_x, arrow_y - arrow_size, arrow_start_x, arrow_y, 2.0, up_arrow_color);
-        draw_line(arrow_start_x, arrow_y - arrow_size, arrow_start_x - arrow_size * 0.6, arrow_y - arrow_size * 0.3, 2.0, up_arrow_color);
-        draw_line(arrow_start_x, arrow_y - arrow_size, arrow_start_x + arrow_size * 0.6, arrow_y - arrow_size * 0.3, 2.0, up_arrow_color);
-
-        draw_text_ex(
-            " / ",
-            arrow_start_x + 15.0,
-            arrow_y,
-            TextParams {
-                font: None,
-                font_size: controls_size as u16,
-                color: get_rainbow_color(right_hue),
-                ..Default::default()
-            },
-        );
-
-        let down_arrow_hue = (self.phase + 120.0) % 360.0;
-        let down_arrow_color = get_rainbow_color(down_arrow_hue);
-        let down_arrow_x = arrow_start_x + 35.0;
-        
-        draw_line(down_arrow_x, arrow_y + arrow_size, down_arrow_x, arrow_y, 2.0, down_arrow_color);
-        draw_line(down_arrow_x, arrow_y + arrow_size, down_arrow_x - arrow_size * 0.6, arrow_y + arrow_size * 0.3, 2.0, down_arrow_color);
-        draw_line(down_arrow_x, arrow_y + arrow_size, down_arrow_x + arrow_size * 0.6, arrow_y + arrow_size * 0.3, 2.0, down_arrow_color);
+        for (seat, side) in [Side::Left, Side::Right].into_iter().enumerate() {
+            let text = format!("{}: {}", side.label(), self.seat_bindings(seat).movement_label());
+            draw_text_ex(
+                &text,
+                SCREEN_WIDTH - 200.0,
+                controls_y_start + 25.0 * (seat + 1) as f32,
+                TextParams {
+                    font: None,
+                    font_size: controls_size as u16,
+                    color: get_rainbow_color((self.phase + 30.0 * (seat + 1) as f32) % 360.0),
+                    ..Default::default()
+                },
+            );
+        }
 
         let score_hue = (self.phase + 90.0) % 360.0;
-        draw_text_ex(
-            "SCORE TO 7 TO WIN",
-            SCREEN_WIDTH - 200.0,
-            controls_y_start + 75.0,
-            TextParams {
-                font: None,
-                font_size: controls_size as u16,
-                color: get_rainbow_color(score_hue),
-                ..Default::default()
+        let info_lines = match items[self.selected_option] {
+            MenuItem::Opponent(stage) if self.campaign.is_unlocked(stage) => {
+                let opponent = &self.ladder[stage];
+                let mut lines = opponent.lines();
+                lines.push(format!("\"{}\"", opponent.taunt));
+                lines
+            }
+            MenuItem::Opponent(stage) => vec!["LOCKED".to_string(), format!("BEAT OPPONENT {} FIRST", stage)],
+            MenuItem::Achievement(i) => {
+                let achievement = &self.achievements[i];
+                vec![
+                    achievement.name.clone(),
+                    achievement.description.clone(),
+                    if self.has_achievement(i) { "UNLOCKED".to_string() } else { "LOCKED".to_string() },
+                ]
+            }
+            _ if self.screen == MenuScreen::EditProfile => self.profiles.entries[self.editing].stats.lines(),
+            MenuItem::Profile(index) => self.profiles.entries[index].stats.lines(),
+            MenuItem::Player(seat) => match self.players[seat].and_then(|index| self.profiles.entries.get(index)) {
+                Some(profile) => profile.stats.lines(),
+                None => vec!["GUEST PLAYER".to_string(), "NO STATS SAVED".to_string()],
             },
-        );
+            _ => self.rules.menu_lines(),
+        };
+        for (i, line) in info_lines.iter().enumerate() {
+            draw_text_ex(
+                line,
+                SCREEN_WIDTH - 200.0,
+                controls_y_start + 75.0 + i as f32 * 20.0,
+                TextParams {
+                    font: None,
+                    font_size: controls_size as u16,
+                    color: get_rainbow_color((score_hue + i as f32 * 30.0) % 360.0),
+                    ..Default::default()
+                },
+            );
+        }
 
         let instruction_size = 20.0;
-        let instruction = "Use ARROWS/W-S to navigate, ENTER/SPACE to select";
+        let instruction = match self.screen {
+            MenuScreen::Main => "ARROWS/W-S to navigate, LEFT/RIGHT to change, ENTER/SPACE to select",
+            MenuScreen::PowerUps => "ENTER/SPACE to toggle a power-up, ESC to go back",
+            MenuScreen::Seats => "ENTER/SPACE to switch a seat between HUMAN and AI, ESC to go back",
+            MenuScreen::HighScores => "Survival high scores, ESC to go back",
+            MenuScreen::Campaign => "ENTER/SPACE to challenge an unlocked opponent, ESC to go back",
+            MenuScreen::Profiles => "ENTER/SPACE to edit a profile, ESC to go back",
+            MenuScreen::Achievements => "Achievements for this profile, ESC to go back",
+            MenuScreen::Settings => match self.capture {
+                Some(Capture::Keys(..)) => "Press the key to bind, ESC to cancel",
+                _ => "LEFT/RIGHT to change, ENTER/SPACE to toggle or rebind keys, ESC to go back",
+            },
+            MenuScreen::Sound => "LEFT/RIGHT to change a volume, ENTER/SPACE to toggle, ESC to go back",
+            MenuScreen::EditProfile => match self.capture {
+                Some(Capture::Name) => "Type a name, ENTER to save, ESC to cancel",
+                Some(Capture::Keys(..)) => "Press the key to bind, ESC to cancel",
+                None => "ENTER/SPACE to edit, LEFT/RIGHT to change color, ESC to go back",
+            },
+        };
         draw_text_ex(
             instruction,
             SCREEN_WIDTH / 2.0 - measure_text(instruction, None, instruction_size as u16, 1.0).width / 2.0,
@@ -0,0 +1,465 @@
+use crate::consts::*;
+use crate::events::{EventListener, GameEvent};
+use crate::synth::{render, transpose, Envelope, Tone, Waveform};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Instrument {
+    Square,
+    Pulse,
+    Triangle,
+    Sine,
+    Saw,
+    Drums,
+}
+
+impl Instrument {
+    fn from_name(name: &str) -> Option<Instrument> {
+        match name {
+            "square" => Some(Instrument::Square),
+            "pulse" => Some(Instrument::Pulse),
+            "triangle" => Some(Instrument::Triangle),
+            "sine" => Some(Instrument::Sine),
+            "saw" => Some(Instrument::Saw),
+            "drums" => Some(Instrument::Drums),
+            _ => None,
+        }
+    }
+
+    fn waveform(self) -> Waveform {
+        match self {
+            Instrument::Square => Waveform::Square(0.5),
+            Instrument::Pulse => Waveform::Square(0.25),
+            Instrument::Triangle => Waveform::Triangle,
+            Instrument::Sine => Waveform::Sine,
+            Instrument::Saw => Waveform::Saw,
+            Instrument::Drums => Waveform::Noise,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Drum {
+    Kick,
+    Snare,
+    Hat,
+}
+
+impl Drum {
+    fn tone(self, start: f32, volume: f32) -> Tone {
+        let tone = match self {
+            Drum::Kick => Tone { slide: 0.25, ..Tone::new(Waveform::Sine, 160.0, 0.1) },
+            Drum::Snare => Tone {
+                envelope: Envelope { attack: 0.001, decay: 0.1, sustain: 0.0, release: 0.02 },
+                ..Tone::new(Waveform::Noise, 800.0, 0.1)
+            },
+            Drum::Hat => Tone {
+                envelope: Envelope::CLICK,
+                volume: 0.5,
+                ..Tone::new(Waveform::Noise, 4000.0, 0.02)
+            },
+        };
+        Tone { start, volume: tone.volume * volume * 2.0, ..tone }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cell {
+    Rest,
+    Hold,
+    Note(f32),
+    Hit(Drum),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Channel {
+    pub name: String,
+    pub instrument: Instrument,
+    pub volume: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Layer {
+    Full,
+    Drums,
+    Bass,
+    Lead,
+}
+
+impl Layer {
+    pub fn for_channel(channel: &Channel) -> Layer {
+        match (channel.instrument, channel.name.as_str()) {
+            (Instrument::Drums, _) => Layer::Drums,
+            (_, "bass") => Layer::Bass,
+            _ => Layer::Lead,
+        }
+    }
+}
+
+pub fn approach(value: f32, target: f32, step: f32) -> f32 {
+    if target > value { (value + step).min(target) } else { (value - step).max(target) }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Song {
+    pub title: String,
+    pub tempo: f32,
+    pub rows_per_beat: u32,
+    pub channels: Vec<Channel>,
+    pub patterns: Vec<(String, Vec<Vec<Cell>>)>,
+    pub order: Vec<usize>,
+}
+
+fn parse_note(token: &str) -> Option<f32> {
+    let mut chars = token.chars();
+    let semitone = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (accidental, octave) = match rest.chars().next()? {
+        '#' => (1, &rest[1..]),
+        'b' => (-1, &rest[1..]),
+        _ => (0, rest),
+    };
+    let octave: i32 = octave.parse().ok()?;
+    let midi = (octave + 1) * 12 + semitone + accidental;
+    Some(440.0 * 2.0_f32.powf((midi - 69) as f32 / 12.0))
+}
+
+fn strip_comment(line: &str) -> &str {
+    let comment = line
+        .char_indices()
+        .find(|(i, c)| *c == '#' && line[..*i].chars().next_back().is_none_or(char::is_whitespace))
+        .map(|(i, _)| i);
+    &line[..comment.unwrap_or(line.len())]
+}
+
+fn parse_cell(token: &str, instrument: Instrument) -> Option<Cell> {
+    match (token, instrument) {
+        (".", _) => Some(Cell::Rest),
+        ("-", _) => Some(Cell::Hold),
+        ("k", Instrument::Drums) => Some(Cell::Hit(Drum::Kick)),
+        ("s", Instrument::Drums) => Some(Cell::Hit(Drum::Snare)),
+        ("h", Instrument::Drums) => Some(Cell::Hit(Drum::Hat)),
+        (_, Instrument::Drums) => None,
+        (note, _) => parse_note(note).map(Cell::Note),
+    }
+}
+
+impl Song {
+    pub fn parse(source: &str) -> Result<Song, String> {
+        let mut song = Song {
+            title: String::new(),
+            tempo: 120.0,
+            rows_per_beat: 4,
+            channels: Vec::new(),
+            patterns: Vec::new(),
+            order: Vec::new(),
+        };
+        let mut order_names: Vec<String> = Vec::new();
+
+        for (number, line) in source.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", number + 1, message);
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens[0] {
+                "title" => song.title = tokens[1..].join(" "),
+                "tempo" => {
+                    song.tempo = tokens
+                        .get(1)
+                        .and_then(|value| value.parse().ok())
+                        .filter(|tempo: &f32| *tempo > 0.0)
+                        .ok_or_else(|| error("tempo must be a number above 0".into()))?;
+                }
+                "rows_per_beat" => {
+                    song.rows_per_beat = tokens
+                        .get(1)
+                        .and_then(|value| value.parse().ok())
+                        .filter(|rows: &u32| *rows > 0)
+                        .ok_or_else(|| error("rows_per_beat must be a whole number above 0".into()))?;
+                }
+                "channel" => {
+                    let [_, name, instrument, volume] = tokens[..] else {
+                        return Err(error("expected: channel <name> <instrument> <volume>".into()));
+                    };
+                    song.channels.push(Channel {
+                        name: name.to_string(),
+                        instrument: Instrument::from_name(instrument)
+                            .ok_or_else(|| error(format!("unknown instrument {}", instrument)))?,
+                        volume: volume.parse().map_err(|_| error(format!("bad volume {}", volume)))?,
+                    });
+                }
+                "pattern" => {
+                    let name = tokens.get(1).ok_or_else(|| error("pattern needs a name".into()))?;
+                    song.patterns.push((name.to_string(), Vec::new()));
+                }
+                "order" => order_names.extend(tokens[1..].iter().map(|name| name.to_string())),
+                _ => {
+                    let Some((_, rows)) = song.patterns.last_mut() else {
+                        return Err(error(format!("unknown directive {}", tokens[0])));
+                    };
+                    if tokens.len() != song.channels.len() {
+                        return Err(error(format!("expected {} cells, found {}", song.channels.len(), tokens.len())));
+                    }
+                    let row = tokens
+                        .iter()
+                        .zip(&song.channels)
+                        .map(|(token, channel)| {
+                            parse_cell(token, channel.instrument).ok_or_else(|| error(format!("bad cell {}", token)))
+                        })
+                        .collect::<Result<Vec<Cell>, String>>()?;
+                    rows.push(row);
+                }
+            }
+        }
+
+        if order_names.is_empty() {
+            order_names = song.patterns.iter().map(|(name, _)| name.clone()).collect();
+        }
+        for name in order_names {
+            let index = song
+                .patterns
+                .iter()
+                .position(|(pattern, _)| *pattern == name)
+                .ok_or_else(|| format!("order: unknown pattern {}", name))?;
+            song.order.push(index);
+        }
+        if song.order.is_empty() || song.channels.is_empty() {
+            return Err("song needs at least one channel and pattern".to_string());
+        }
+        Ok(song)
+    }
+
+    pub fn row_time(&self) -> f32 {
+        60.0 / self.tempo / self.rows_per_beat as f32
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &Vec<Cell>> {
+        self.order.iter().flat_map(|index| self.patterns[*index].1.iter())
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.rows().count() as f32 * self.row_time()
+    }
+
+    pub fn channel_tones(&self, channel: usize) -> Vec<Tone> {
+        let row_time = self.row_time();
+        let Channel { instrument, volume, .. } = self.channels[channel];
+        let mut tones = Vec::new();
+        let mut held: Option<Tone> = None;
+        for (row, cells) in self.rows().enumerate() {
+            let start = row as f32 * row_time;
+            match cells[channel] {
+                Cell::Hold => {
+                    if let Some(tone) = &mut held {
+                        tone.length += row_time;
+                    }
+                    continue;
+                }
+                Cell::Hit(drum) => tones.push(drum.tone(start, volume)),
+                Cell::Rest => {}
+                Cell::Note(frequency) => {
+                    tones.extend(held.take());
+                    held = Some(Tone {
+                        start,
+                        volume,
+                        envelope: Envelope::NOTE,
+                        ..Tone::new(instrument.waveform(), frequency, row_time * 0.9)
+                    });
+                    continue;
+                }
+            }
+            tones.extend(held.take());
+        }
+        tones.extend(held);
+        tones
+    }
+
+    pub fn transposed(&self, semitones: f32) -> Song {
+        let mut song = self.clone();
+        for (_, rows) in &mut song.patterns {
+            for cell in rows.iter_mut().flatten() {
+                if let Cell::Note(frequency) = cell {
+                    *frequency = transpose(*frequency, semitones);
+                }
+            }
+        }
+        song
+    }
+
+    pub fn render_channel(&self, channel: usize) -> Vec<f32> {
+        self.render_loop(&self.channel_tones(channel))
+    }
+
+    pub fn render(&self) -> Vec<f32> {
+        let tones: Vec<Tone> = (0..self.channels.len()).flat_map(|channel| self.channel_tones(channel)).collect();
+        self.render_loop(&tones)
+    }
+
+    fn render_loop(&self, tones: &[Tone]) -> Vec<f32> {
+        let mut samples = render(tones);
+        let length = (self.duration() * SYNTH_SAMPLE_RATE as f32) as usize;
+        if samples.len() > length {
+            let tail = samples.split_off(length);
+            for (sample, overflow) in samples.iter_mut().zip(tail) {
+                *sample = (*sample + overflow).clamp(-1.0, 1.0);
+            }
+        }
+        samples.resize(length, 0.0);
+        samples
+    }
+}
+
+pub struct MusicController {
+    rally: u32,
+    speed: f32,
+    match_point: bool,
+    finished: bool,
+    dropout: f32,
+    levels: [f32; 3],
+    key: f32,
+}
+
+impl MusicController {
+    pub fn new() -> Self {
+        Self {
+            rally: 0,
+            speed: 0.0,
+            match_point: false,
+            finished: false,
+            dropout: 0.0,
+            levels: [1.0, 0.0, 0.0],
+            key: 0.0,
+        }
+    }
+
+    pub fn intensity(&self) -> f32 {
+        let rally = self.rally as f32 / MUSIC_FULL_RALLY as f32;
+        let speed = (self.speed - BALL_INITIAL_SPEED) / (BALL_MAX_SPEED - BALL_INITIAL_SPEED);
+        rally.max(speed).clamp(0.0, 1.0)
+    }
+
+    fn target(&self, layer: Layer) -> f32 {
+        if self.finished {
+            return 1.0;
+        }
+        if self.dropout > 0.0 {
+            return 0.0;
+        }
+        let threshold = match layer {
+            Layer::Full | Layer::Drums => 0.0,
+            Layer::Bass => MUSIC_BASS_INTENSITY,
+            Layer::Lead => MUSIC_LEAD_INTENSITY,
+        };
+        if self.intensity() >= threshold { 1.0 } else { 0.0 }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.dropout = (self.dropout - dt).max(0.0);
+        let fade = if self.dropout > 0.0 { MUSIC_DROPOUT_FADE_TIME } else { MUSIC_LAYER_FADE_TIME };
+        for (index, layer) in [Layer::Drums, Layer::Bass, Layer::Lead].into_iter().enumerate() {
+            self.levels[index] = approach(self.levels[index], self.target(layer), dt / fade);
+        }
+        self.key = approach(self.key, if self.match_point { 1.0 } else { 0.0 }, dt / MUSIC_LAYER_FADE_TIME);
+    }
+
+    pub fn stem_level(&self, layer: Layer, shifted: bool) -> f32 {
+        let level = match layer {
+            Layer::Full => return 1.0,
+            Layer::Drums => return self.levels[0],
+            Layer::Bass => self.levels[1],
+            Layer::Lead => self.levels[2],
+        };
+        level * if shifted { self.key } else { 1.0 - self.key }
+    }
+}
+
+impl EventListener for MusicController {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::Serve { .. } => {
+                self.rally = 0;
+                self.speed = 0.0;
+                self.finished = false;
+            }
+            GameEvent::PaddleHit { speed, .. } => {
+                self.rally += 1;
+                self.speed = speed;
+            }
+            GameEvent::Score { .. } | GameEvent::LifeLost { .. } => {
+                self.rally = 0;
+                self.speed = 0.0;
+                self.match_point = false;
+                self.dropout = MUSIC_STING_TIME;
+            }
+            GameEvent::MatchPoint { .. } => self.match_point = true,
+            GameEvent::MatchEnd { .. } => self.finished = true,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHARPS: &str = "
+tempo 120
+channel lead square 0.5 # the melody
+channel bass triangle 0.5
+pattern intro
+F#4 C#3  # sharp notes keep their octave
+Bb4 -
+";
+
+    #[test]
+    fn parses_sharp_notes_before_a_trailing_comment() {
+        let song = Song::parse(SHARPS).unwrap();
+        let rows = &song.patterns[0].1;
+        assert_eq!(rows.len(), 2);
+        let Cell::Note(frequency) = rows[0][0] else {
+            panic!("expected a note, found {:?}", rows[0][0]);
+        };
+        assert!((frequency - 369.99).abs() < 0.01);
+        assert!(matches!(rows[0][1], Cell::Note(_)));
+        assert_eq!(rows[1][1], Cell::Hold);
+    }
+
+    #[test]
+    fn rejects_tempo_and_rows_per_beat_that_are_not_positive() {
+        for (line, directive) in [("tempo 0", "tempo"), ("tempo -90", "tempo"), ("rows_per_beat 0", "rows_per_beat")] {
+            let source = format!("{}\nchannel lead square 0.5\npattern a\nC4\n", line);
+            let err = Song::parse(&source).unwrap_err();
+            assert!(err.starts_with("line 1:") && err.contains(directive), "{}", err);
+        }
+    }
+
+    #[test]
+    fn bundled_songs_render_their_full_length_with_sound() {
+        let bundled = [include_str!("../data/music/menu.txt"), include_str!("../data/music/game.txt")];
+        for source in bundled {
+            let song = Song::parse(source).unwrap();
+            let rows: usize = song.order.iter().map(|index| song.patterns[*index].1.len()).sum();
+            let seconds = rows as f32 * 60.0 / song.tempo / song.rows_per_beat as f32;
+            let expected = (seconds * SYNTH_SAMPLE_RATE as f32) as usize;
+
+            let tones: Vec<Tone> = (0..song.channels.len()).flat_map(|channel| song.channel_tones(channel)).collect();
+            let peak = render(&tones).iter().fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+            assert!(peak > 0.05, "{} is silent", song.title);
+
+            assert_eq!(song.render().len(), expected, "{}", song.title);
+            for channel in 0..song.channels.len() {
+                assert_eq!(song.render_channel(channel).len(), expected, "{}", song.title);
+            }
+        }
+    }
+}
@@ -1,39 +1,94 @@
 use macroquad::prelude::*;
 use crate::consts::*;
 use crate::effects::*;
+use crate::rules::Side;
+use crate::controls::KeyBindings;
+use crate::ai::AiStyle;
 
 pub struct Paddle {
+    pub side: Side,
     pub position: Vec2,
-    pub velocity: f32,
+    pub home: Vec2,
+    pub velocity: Vec2,
     pub hue: f32,
     pub is_ai: bool,
+    pub ai: AiStyle,
     pub trail: Trail,
+    pub height_scale: f32,
+    pub travel: (f32, f32),
+    pub zone_depth: f32,
+    pub bindings: KeyBindings,
 }
 
 impl Paddle {
-    pub fn new(x: f32, is_ai: bool) -> Self {
+    pub fn new(side: Side, depth: f32, is_ai: bool, bindings: KeyBindings) -> Self {
+        let inset = PADDLE_MARGIN + PADDLE_WIDTH / 2.0;
+        let (position, travel_end) = match side {
+            Side::Left => (Vec2::new(inset, SCREEN_HEIGHT / 2.0), SCREEN_HEIGHT),
+            Side::Right => (Vec2::new(SCREEN_WIDTH - inset, SCREEN_HEIGHT / 2.0), SCREEN_HEIGHT),
+            Side::Top => (Vec2::new(SCREEN_WIDTH / 2.0, inset), SCREEN_WIDTH),
+            Side::Bottom => (Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT - inset), SCREEN_WIDTH),
+        };
+        let position = position + side.normal() * depth;
         Self {
-            position: Vec2::new(x, SCREEN_HEIGHT / 2.0),
-            velocity: 0.0,
-            hue: if x < SCREEN_WIDTH / 2.0 { 0.0 } else { 180.0 },
+            side,
+            position,
+            home: position,
+            velocity: Vec2::ZERO,
+            hue: side.hue(),
             is_ai,
+            ai: AiStyle::standard(),
             trail: Trail::new(),
+            height_scale: 1.0,
+            travel: (0.0, travel_end),
+            zone_depth: 0.0,
+            bindings,
         }
     }
 
-    pub fn update(&mut self, dt: f32, ball_position: Option<Vec2>, keys: (bool, bool)) {
-        let target_velocity = if self.is_ai {
-            self.ai_velocity(ball_position)
+    pub fn set_zone_depth(&mut self, zone: f32) {
+        let to_center = (Vec2::new(SCREEN_WIDTH, SCREEN_HEIGHT) / 2.0 - self.home).dot(self.side.normal());
+        self.zone_depth = zone.min(to_center - 40.0).max(0.0);
+    }
+
+    pub fn height(&self) -> f32 {
+        PADDLE_HEIGHT * self.height_scale
+    }
+
+    pub fn axis(&self) -> Vec2 {
+        if self.side.is_horizontal() { Vec2::X } else { Vec2::Y }
+    }
+
+    fn offset_along(&self) -> f32 {
+        self.position.dot(self.axis())
+    }
+
+    pub fn advance(&self) -> f32 {
+        (self.position - self.home).dot(self.side.normal())
+    }
+
+    pub fn update(&mut self, dt: f32, ball_position: Option<Vec2>, keys: (bool, bool), depth_keys: (bool, bool)) {
+        let (target_along, target_depth) = if self.is_ai {
+            (self.ai_velocity(ball_position), self.ai_depth_velocity())
         } else {
-            self.player_velocity(keys)
+            (self.player_velocity(keys), self.player_velocity(depth_keys))
         };
+        let normal = self.side.normal();
+        let target_velocity = self.axis() * target_along + normal * target_depth;
 
         self.velocity += (target_velocity - self.velocity) * 15.0 * dt;
-        self.position.y += self.velocity * dt;
-
-        self.position.y = self.position.y
-            .max(PADDLE_HEIGHT / 2.0)
-            .min(SCREEN_HEIGHT - PADDLE_HEIGHT / 2.0);
+        let half = self.height() / 2.0;
+        let along = (self.offset_along() + self.velocity.dot(self.axis()) * dt).clamp(self.travel.0 + half, self.travel.1 - half);
+        let depth = (self.advance() + self.velocity.dot(normal) * dt).clamp(0.0, self.zone_depth);
+        if depth == 0.0 || depth == self.zone_depth {
+            self.velocity -= normal * self.velocity.dot(normal);
+        }
+        self.position = self.home + normal * depth;
+        if self.side.is_horizontal() {
+            self.position.x = along;
+        } else {
+            self.position.y = along;
+        }
 
         self.trail.update(dt);
         self.trail.add_point(Vec2::new(self.position.x, self.position.y), self.hue);
@@ -47,14 +102,35 @@ impl Paddle {
         }
     }
 
+    fn ai_depth_velocity(&self) -> f32 {
+        if self.ai.personality.presses_forward() {
+            if self.advance() < self.zone_depth - 5.0 { PADDLE_SPEED * 0.5 } else { 0.0 }
+        } else if self.advance() > 5.0 {
+            -PADDLE_SPEED * 0.5
+        } else {
+            0.0
+        }
+    }
+
+    pub fn zone_rect(&self) -> Rect {
+        let normal = self.side.normal();
+        let near = self.home - normal * (PADDLE_WIDTH / 2.0);
+        let far = self.home + normal * (self.zone_depth + PADDLE_WIDTH / 2.0);
+        let (start, end) = (self.travel.0, self.travel.1);
+        if self.side.is_horizontal() {
+            Rect::new(start, near.y.min(far.y), end - start, (far.y - near.y).abs())
+        } else {
+            Rect::new(near.x.min(far.x), start, (far.x - near.x).abs(), end - start)
+        }
+    }
+
     fn ai_velocity(&self, ball_position: Option<Vec2>) -> f32 {
         if let Some(ball_pos) = ball_position {
-            let target_y = ball_pos.y;
-            let diff = target_y - self.position.y;
-            let threshold = 20.0;
+            let diff = ball_pos.dot(self.axis()) - self.offset_along();
+            let threshold = self.ai.difficulty.dead_zone();
 
             if diff.abs() > threshold {
-                diff.signum() * PADDLE_SPEED * 0.85
+                diff.signum() * PADDLE_SPEED * self.ai.difficulty.speed_factor()
             } else {
                 0.0
             }
@@ -64,11 +140,16 @@ impl Paddle {
     }
 
     pub fn get_rect(&self) -> Rect {
+        let (width, height) = if self.side.is_horizontal() {
+            (self.height(), PADDLE_WIDTH)
+        } else {
+            (PADDLE_WIDTH, self.height())
+        };
         Rect::new(
-            self.position.x - PADDLE_WIDTH / 2.0,
-            self.position.y - PADDLE_HEIGHT / 2.0,
-            PADDLE_WIDTH,
-            PADDLE_HEIGHT,
+            self.position.x - width / 2.0,
+            self.position.y - height / 2.0,
+            width,
+            height,
         )
     }
 
@@ -79,7 +160,7 @@ impl Paddle {
         
         draw_glow(
             self.position,
-            PADDLE_HEIGHT / 2.0,
+            self.height() / 2.0,
             color,
             1.0,
         );
@@ -97,8 +178,26 @@ impl Paddle {
         );
     }
 
+    pub fn hit_offset(&self, position: Vec2) -> f32 {
+        ((position - self.get_center()).dot(self.axis()) / (self.height() / 2.0)).clamp(-1.0, 1.0)
+    }
+
     pub fn get_center(&self) -> Vec2 {
         self.position
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zone_depth_stops_short_of_the_center_line() {
+        let mut paddle = Paddle::new(Side::Left, 0.0, false, KeyBindings::for_seat(0));
+        paddle.set_zone_depth(10_000.0);
+        assert_eq!(paddle.zone_depth, SCREEN_WIDTH / 2.0 - paddle.home.x - 40.0);
+        paddle.set_zone_depth(60.0);
+        assert_eq!(paddle.zone_depth, 60.0);
+    }
+}
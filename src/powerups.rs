@@ -0,0 +1,386 @@
+use macroquad::prelude::*;
+use crate::consts::*;
+use crate::effects::*;
+use crate::rules::Side;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PowerUpKind {
+    BigPaddle,
+    SmallPaddle,
+    FastBall,
+    SlowBall,
+    Shield,
+    ReverseControls,
+    MultiBall,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 7] = [
+        PowerUpKind::BigPaddle,
+        PowerUpKind::SmallPaddle,
+        PowerUpKind::FastBall,
+        PowerUpKind::SlowBall,
+        PowerUpKind::Shield,
+        PowerUpKind::ReverseControls,
+        PowerUpKind::MultiBall,
+    ];
+
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|kind| *kind == self).unwrap_or(0)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PowerUpKind::BigPaddle => "BIG PADDLE",
+            PowerUpKind::SmallPaddle => "SMALL PADDLE",
+            PowerUpKind::FastBall => "FAST BALL",
+            PowerUpKind::SlowBall => "SLOW BALL",
+            PowerUpKind::Shield => "SHIELD WALL",
+            PowerUpKind::ReverseControls => "REVERSE CONTROLS",
+            PowerUpKind::MultiBall => "MULTI-BALL",
+        }
+    }
+
+    pub fn icon(self) -> &'static str {
+        match self {
+            PowerUpKind::BigPaddle => "+",
+            PowerUpKind::SmallPaddle => "-",
+            PowerUpKind::FastBall => ">>",
+            PowerUpKind::SlowBall => "<<",
+            PowerUpKind::Shield => "#",
+            PowerUpKind::ReverseControls => "?",
+            PowerUpKind::MultiBall => "oo",
+        }
+    }
+
+    pub fn hue(self) -> f32 {
+        match self {
+            PowerUpKind::BigPaddle => 120.0,
+            PowerUpKind::SmallPaddle => 0.0,
+            PowerUpKind::FastBall => 30.0,
+            PowerUpKind::SlowBall => 200.0,
+            PowerUpKind::Shield => 260.0,
+            PowerUpKind::ReverseControls => 300.0,
+            PowerUpKind::MultiBall => 60.0,
+        }
+    }
+
+    pub fn duration(self) -> f32 {
+        match self {
+            PowerUpKind::Shield => 6.0,
+            PowerUpKind::ReverseControls => 5.0,
+            PowerUpKind::MultiBall => 0.0,
+            _ => 8.0,
+        }
+    }
+
+    pub fn hits_opponent(self) -> bool {
+        matches!(self, PowerUpKind::SmallPaddle | PowerUpKind::ReverseControls)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PowerUpSet {
+    enabled: [bool; 7],
+}
+
+impl PowerUpSet {
+    pub fn none() -> Self {
+        Self { enabled: [false; 7] }
+    }
+
+    pub fn all() -> Self {
+        Self { enabled: [true; 7] }
+    }
+
+    pub fn contains(&self, kind: PowerUpKind) -> bool {
+        self.enabled[kind.index()]
+    }
+
+    pub fn toggle(&mut self, kind: PowerUpKind) {
+        self.enabled[kind.index()] = !self.enabled[kind.index()];
+    }
+
+    pub fn kinds(&self) -> Vec<PowerUpKind> {
+        PowerUpKind::ALL.iter().copied().filter(|kind| self.contains(*kind)).collect()
+    }
+
+    pub fn count(&self) -> usize {
+        self.enabled.iter().filter(|enabled| **enabled).count()
+    }
+}
+
+pub struct PowerUp {
+    pub position: Vec2,
+    pub kind: PowerUpKind,
+    pub lifetime: f32,
+}
+
+pub struct ActiveEffect {
+    pub kind: PowerUpKind,
+    pub side: Side,
+    pub remaining: f32,
+}
+
+pub struct PowerUpSystem {
+    enabled: PowerUpSet,
+    pub pickups: Vec<PowerUp>,
+    pub active: Vec<ActiveEffect>,
+    spawn_timer: f32,
+}
+
+impl PowerUpSystem {
+    pub fn new(enabled: PowerUpSet) -> Self {
+        Self {
+            enabled,
+            pickups: Vec::new(),
+            active: Vec::new(),
+            spawn_timer: POWERUP_SPAWN_INTERVAL,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32, effects: &EffectSettings) -> Vec<Particle> {
+        let mut particles = Vec::new();
+
+        for effect in &mut self.active {
+            effect.remaining -= dt;
+        }
+        self.active.retain(|effect| effect.remaining > 0.0);
+
+        for pickup in &mut self.pickups {
+            pickup.lifetime -= dt;
+            if pickup.lifetime <= 0.0 {
+                particles.extend(create_particle_explosion(pickup.position, pickup.kind.hue(), effects.particles(6)));
+            }
+        }
+        self.pickups.retain(|pickup| pickup.lifetime > 0.0);
+
+        let kinds = self.enabled.kinds();
+        if kinds.is_empty() {
+            return particles;
+        }
+
+        self.spawn_timer -= dt;
+        if self.spawn_timer <= 0.0 {
+            self.spawn_timer = POWERUP_SPAWN_INTERVAL * macroquad::rand::gen_range(0.7, 1.3);
+            if self.pickups.len() < POWERUP_MAX_PICKUPS {
+                let kind = kinds[macroquad::rand::gen_range(0, kinds.len())];
+                let position = Vec2::new(
+                    macroquad::rand::gen_range(SCREEN_WIDTH * 0.3, SCREEN_WIDTH * 0.7),
+                    macroquad::rand::gen_range(80.0, SCREEN_HEIGHT - 80.0),
+                );
+                particles.extend(create_particle_explosion(position, kind.hue(), effects.particles(8)));
+                self.pickups.push(PowerUp {
+                    position,
+                    kind,
+                    lifetime: POWERUP_LIFETIME,
+                });
+            }
+        }
+
+        particles
+    }
+
+    pub fn collect(&mut self, ball_rect: Rect, collector: Side) -> Option<(PowerUpKind, Vec2)> {
+        let ball_center = ball_rect.center();
+        let reach = POWERUP_RADIUS + ball_rect.w / 2.0;
+        let index = self
+            .pickups
+            .iter()
+            .position(|pickup| pickup.position.distance(ball_center) < reach)?;
+        let pickup = self.pickups.remove(index);
+        if pickup.kind == PowerUpKind::MultiBall {
+            return Some((pickup.kind, pickup.position));
+        }
+
+        let side = if pickup.kind.hits_opponent() { collector.opponent() } else { collector };
+        let opposite = match pickup.kind {
+            PowerUpKind::BigPaddle => Some(PowerUpKind::SmallPaddle),
+            PowerUpKind::SmallPaddle => Some(PowerUpKind::BigPaddle),
+            PowerUpKind::FastBall => Some(PowerUpKind::SlowBall),
+            PowerUpKind::SlowBall => Some(PowerUpKind::FastBall),
+            _ => None,
+        };
+        let global = matches!(pickup.kind, PowerUpKind::FastBall | PowerUpKind::SlowBall);
+        self.active.retain(|effect| {
+            let same_target = global || effect.side == side;
+            !(same_target && (effect.kind == pickup.kind || Some(effect.kind) == opposite))
+        });
+        self.active.push(ActiveEffect {
+            kind: pickup.kind,
+            side,
+            remaining: pickup.kind.duration(),
+        });
+
+        Some((pickup.kind, pickup.position))
+    }
+
+    pub fn is_active(&self, side: Side, kind: PowerUpKind) -> bool {
+        self.active.iter().any(|effect| effect.side == side && effect.kind == kind)
+    }
+
+    pub fn paddle_scale(&self, side: Side) -> f32 {
+        if self.is_active(side, PowerUpKind::BigPaddle) {
+            1.5
+        } else if self.is_active(side, PowerUpKind::SmallPaddle) {
+            0.6
+        } else {
+            1.0
+        }
+    }
+
+    pub fn ball_time_scale(&self) -> f32 {
+        if self.active.iter().any(|effect| effect.kind == PowerUpKind::FastBall) {
+            1.4
+        } else if self.active.iter().any(|effect| effect.kind == PowerUpKind::SlowBall) {
+            0.65
+        } else {
+            1.0
+        }
+    }
+
+    pub fn draw(&self, phase: f32) {
+        for pickup in &self.pickups {
+            let bob = (phase.to_radians() * 6.0 + pickup.position.x).sin() * 4.0;
+            let position = pickup.position + Vec2::new(0.0, bob);
+            let fade = pickup.lifetime.min(1.0);
+            let color = get_rainbow_color((pickup.kind.hue() + phase * 0.5) % 360.0);
+
+            draw_glow(position, POWERUP_RADIUS, color, fade);
+            draw_circle(position.x, position.y, POWERUP_RADIUS, Color::new(color.r * 0.3, color.g * 0.3, color.b * 0.3, 0.8 * fade));
+            draw_circle_lines(position.x, position.y, POWERUP_RADIUS, 3.0, Color::new(color.r, color.g, color.b, fade));
+
+            let icon = pickup.kind.icon();
+            let icon_size = 24.0;
+            draw_text_ex(
+                icon,
+                position.x - measure_text(icon, None, icon_size as u16, 1.0).width / 2.0,
+                position.y + icon_size * 0.3,
+                TextParams {
+                    font: None,
+                    font_size: icon_size as u16,
+                    color: Color::new(1.0, 1.0, 1.0, fade),
+                    ..Default::default()
+                },
+            );
+        }
+
+        for effect in &self.active {
+            if effect.kind != PowerUpKind::Shield {
+                continue;
+            }
+            let color = get_rainbow_color((PowerUpKind::Shield.hue() + phase) % 360.0);
+            let alpha = if effect.remaining < 1.5 { (effect.remaining * 10.0).sin().abs() } else { 0.9 };
+            let color = Color::new(color.r, color.g, color.b, alpha);
+            match effect.side {
+                Side::Left => draw_rectangle(SHIELD_INSET - 3.0, 0.0, 6.0, SCREEN_HEIGHT, color),
+                Side::Right => draw_rectangle(SCREEN_WIDTH - SHIELD_INSET - 3.0, 0.0, 6.0, SCREEN_HEIGHT, color),
+                Side::Top => draw_rectangle(0.0, SHIELD_INSET - 3.0, SCREEN_WIDTH, 6.0, color),
+                Side::Bottom => draw_rectangle(0.0, SCREEN_HEIGHT - SHIELD_INSET - 3.0, SCREEN_WIDTH, 6.0, color),
+            }
+        }
+    }
+
+    pub fn draw_hud(&self, phase: f32) {
+        for side in Side::ALL {
+            let effects: Vec<&ActiveEffect> = self.active.iter().filter(|effect| effect.side == side).collect();
+            for (i, effect) in effects.iter().enumerate() {
+                let (x, y) = match side {
+                    Side::Left => (SCREEN_WIDTH / 4.0 + 45.0 + i as f32 * 34.0, 20.0),
+                    Side::Right => (SCREEN_WIDTH * 3.0 / 4.0 - 75.0 - i as f32 * 34.0, 20.0),
+                    Side::Top => (SCREEN_WIDTH / 2.0 + 60.0 + i as f32 * 34.0, 60.0),
+                    Side::Bottom => (SCREEN_WIDTH / 2.0 + 60.0 + i as f32 * 34.0, SCREEN_HEIGHT - 100.0),
+                };
+                let color = get_rainbow_color((effect.kind.hue() + phase * 0.5) % 360.0);
+
+                draw_rectangle(x, y, 30.0, 30.0, Color::new(color.r * 0.3, color.g * 0.3, color.b * 0.3, 0.7));
+                draw_rectangle_lines(x, y, 30.0, 30.0, 2.0, color);
+
+                let icon = effect.kind.icon();
+                let icon_size = 20.0;
+                draw_text_ex(
+                    icon,
+                    x + 15.0 - measure_text(icon, None, icon_size as u16, 1.0).width / 2.0,
+                    y + 21.0,
+                    TextParams {
+                        font: None,
+                        font_size: icon_size as u16,
+                        color: WHITE,
+                        ..Default::default()
+                    },
+                );
+
+                let fraction = (effect.remaining / effect.kind.duration()).clamp(0.0, 1.0);
+                draw_rectangle(x, y + 33.0, 30.0 * fraction, 4.0, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn system_with(kinds: &[PowerUpKind]) -> PowerUpSystem {
+        let mut system = PowerUpSystem::new(PowerUpSet::none());
+        for (i, kind) in kinds.iter().enumerate() {
+            system.pickups.push(PowerUp {
+                position: Vec2::new(100.0 + i as f32 * 200.0, 300.0),
+                kind: *kind,
+                lifetime: POWERUP_LIFETIME,
+            });
+        }
+        system
+    }
+
+    fn ball_at(x: f32) -> Rect {
+        Rect::new(x - BALL_SIZE / 2.0, 300.0 - BALL_SIZE / 2.0, BALL_SIZE, BALL_SIZE)
+    }
+
+    #[test]
+    fn collecting_applies_to_the_collector_or_their_opponent() {
+        let mut system = system_with(&[PowerUpKind::BigPaddle, PowerUpKind::SmallPaddle, PowerUpKind::BigPaddle]);
+        assert_eq!(system.collect(ball_at(200.0), Side::Left), None);
+
+        assert_eq!(system.collect(ball_at(100.0), Side::Left), Some((PowerUpKind::BigPaddle, Vec2::new(100.0, 300.0))));
+        assert_eq!(system.paddle_scale(Side::Left), 1.5);
+
+        assert!(system.collect(ball_at(300.0), Side::Left).is_some());
+        assert_eq!(system.paddle_scale(Side::Left), 1.5);
+        assert_eq!(system.paddle_scale(Side::Right), 0.6);
+
+        assert!(system.collect(ball_at(500.0), Side::Right).is_some());
+        assert_eq!(system.paddle_scale(Side::Right), 1.5);
+        assert!(!system.is_active(Side::Right, PowerUpKind::SmallPaddle));
+        assert!(system.pickups.is_empty());
+    }
+
+    #[test]
+    fn ball_speed_effects_replace_each_other_for_both_sides() {
+        let mut system = system_with(&[PowerUpKind::FastBall, PowerUpKind::SlowBall]);
+        system.collect(ball_at(100.0), Side::Left);
+        assert_eq!(system.ball_time_scale(), 1.4);
+        system.collect(ball_at(300.0), Side::Right);
+        assert_eq!(system.ball_time_scale(), 0.65);
+        assert_eq!(system.active.len(), 1);
+    }
+
+    #[test]
+    fn effects_and_pickups_expire() {
+        let mut system = system_with(&[PowerUpKind::Shield, PowerUpKind::ReverseControls]);
+        system.collect(ball_at(100.0), Side::Left);
+        let effects = EffectSettings::default();
+
+        assert!(system.update(PowerUpKind::Shield.duration() - 0.5, &effects).is_empty());
+        assert!(system.is_active(Side::Left, PowerUpKind::Shield));
+        system.update(1.0, &effects);
+        assert!(!system.is_active(Side::Left, PowerUpKind::Shield));
+
+        let particles = system.update(POWERUP_LIFETIME, &effects);
+        assert!(system.pickups.is_empty());
+        assert_eq!(particles.len(), effects.particles(6));
+        let quiet = EffectSettings { intensity: 0.0, ..effects };
+        let mut system = system_with(&[PowerUpKind::Shield]);
+        assert!(system.update(POWERUP_LIFETIME, &quiet).is_empty());
+    }
+}
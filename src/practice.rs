@@ -0,0 +1,431 @@
+use macroquad::prelude::*;
+use crate::consts::*;
+use crate::effects::*;
+use crate::ball::Ball;
+use crate::paddle::Paddle;
+use crate::rules::{MatchRules, Side};
+use crate::settings::Settings;
+use crate::events::{EventBus, GameEvent};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Drill {
+    Random,
+    Straight,
+    Alternate,
+    Sweep,
+    SpeedUp,
+}
+
+impl Drill {
+    pub const ALL: [Drill; 5] = [Drill::Random, Drill::Straight, Drill::Alternate, Drill::Sweep, Drill::SpeedUp];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Drill::Random => "RANDOM",
+            Drill::Straight => "STRAIGHT",
+            Drill::Alternate => "ALTERNATE",
+            Drill::Sweep => "SWEEP",
+            Drill::SpeedUp => "SPEED UP",
+        }
+    }
+
+    fn shot(self, index: u32, settings: &MachineSettings) -> Shot {
+        let center = SCREEN_HEIGHT / 2.0;
+        match self {
+            Drill::Random => Shot {
+                origin_y: macroquad::rand::gen_range(100.0, SCREEN_HEIGHT - 100.0),
+                angle: macroquad::rand::gen_range(-35.0, 35.0),
+                speed: settings.speed * macroquad::rand::gen_range(0.85, 1.2),
+            },
+            Drill::Straight => Shot {
+                origin_y: center,
+                angle: 0.0,
+                speed: settings.speed,
+            },
+            Drill::Alternate => Shot {
+                origin_y: center,
+                angle: if index.is_multiple_of(2) { 25.0 } else { -25.0 },
+                speed: settings.speed,
+            },
+            Drill::Sweep => {
+                let t = index as f32 / (settings.shots.max(2) - 1) as f32;
+                Shot {
+                    origin_y: center,
+                    angle: -35.0 + 70.0 * t,
+                    speed: settings.speed,
+                }
+            }
+            Drill::SpeedUp => Shot {
+                origin_y: center + macroquad::rand::gen_range(-60.0, 60.0),
+                angle: macroquad::rand::gen_range(-10.0, 10.0),
+                speed: (settings.speed * (1.0 + index as f32 * 0.06)).min(BALL_MAX_SPEED),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MachineSettings {
+    pub drill: Drill,
+    pub speed: f32,
+    pub interval: f32,
+    pub shots: u32,
+}
+
+impl MachineSettings {
+    pub fn standard() -> Self {
+        Self {
+            drill: Drill::Random,
+            speed: PRACTICE_SPEEDS[1],
+            interval: PRACTICE_INTERVALS[1],
+            shots: PRACTICE_SHOT_COUNTS[1],
+        }
+    }
+}
+
+struct Shot {
+    origin_y: f32,
+    angle: f32,
+    speed: f32,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct DrillStats {
+    pub fired: u32,
+    pub returned: u32,
+    pub on_target: u32,
+    pub missed: u32,
+    pub streak: u32,
+    pub best_streak: u32,
+    pub return_speed_total: f32,
+}
+
+impl DrillStats {
+    fn percent(&self, count: u32) -> u32 {
+        if self.fired == 0 {
+            0
+        } else {
+            (count as f32 / self.fired as f32 * 100.0).round() as u32
+        }
+    }
+
+    pub fn average_return_speed(&self) -> f32 {
+        if self.returned == 0 {
+            0.0
+        } else {
+            self.return_speed_total / self.returned as f32
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            format!("SHOTS FIRED: {}", self.fired),
+            format!("RETURNED: {} ({}%)", self.returned, self.percent(self.returned)),
+            format!("ON TARGET: {} ({}%)", self.on_target, self.percent(self.on_target)),
+            format!("MISSED: {}", self.missed),
+            format!("BEST STREAK: {}", self.best_streak),
+            format!("AVG RETURN SPEED: {:.0}", self.average_return_speed()),
+        ]
+    }
+}
+
+pub enum PracticeResult {
+    Continue,
+    Exit,
+}
+
+pub struct PracticeSession {
+    settings: MachineSettings,
+    spin: bool,
+    paddle: Paddle,
+    balls: Vec<Ball>,
+    machine_y: f32,
+    machine_angle: f32,
+    machine_flash: f32,
+    fire_timer: f32,
+    stats: DrillStats,
+    particles: Vec<Particle>,
+    effects: EffectSettings,
+    phase: f32,
+    finished: bool,
+    events: EventBus,
+}
+
+impl PracticeSession {
+    pub fn new(rules: MatchRules, settings: &Settings) -> Self {
+        let mut paddle = Paddle::new(Side::Left, 0.0, false, settings.bindings[0]);
+        paddle.set_zone_depth(rules.paddle_zone);
+        Self {
+            settings: rules.machine,
+            spin: rules.spin,
+            paddle,
+            balls: Vec::new(),
+            machine_y: SCREEN_HEIGHT / 2.0,
+            machine_angle: 0.0,
+            machine_flash: 0.0,
+            fire_timer: SERVE_COUNTDOWN,
+            stats: DrillStats::default(),
+            particles: Vec::new(),
+            effects: settings.effects,
+            phase: 0.0,
+            finished: false,
+            events: EventBus::default(),
+        }
+    }
+
+    fn restart(&mut self) {
+        self.balls.clear();
+        self.stats = DrillStats::default();
+        self.fire_timer = SERVE_COUNTDOWN;
+        self.finished = false;
+    }
+
+    fn target_range(&self) -> (f32, f32) {
+        (SCREEN_HEIGHT / 2.0 - PRACTICE_TARGET_SIZE / 2.0, SCREEN_HEIGHT / 2.0 + PRACTICE_TARGET_SIZE / 2.0)
+    }
+
+    fn fire(&mut self) {
+        let shot = self.settings.drill.shot(self.stats.fired, &self.settings);
+        let mut ball = Ball::new();
+        ball.spin_enabled = self.spin;
+        ball.hue = (self.stats.fired as f32 * 37.0) % 360.0;
+        ball.speed = shot.speed;
+        ball.hold(0.0, Vec2::new(SCREEN_WIDTH - PADDLE_MARGIN - 20.0, shot.origin_y));
+        ball.launch(-Vec2::X, -shot.angle.to_radians());
+
+        self.machine_y = shot.origin_y;
+        self.machine_angle = shot.angle;
+        self.machine_flash = 1.0;
+        self.particles.extend(create_particle_explosion(ball.position, ball.hue, self.effects.particles(6)));
+        self.events.emit(GameEvent::Serve { server: Side::Right });
+        self.balls.push(ball);
+        self.stats.fired += 1;
+    }
+
+    pub fn events(&self) -> &[GameEvent] {
+        self.events.events()
+    }
+
+    pub fn ball_position(&self) -> Option<Vec2> {
+        self.balls.first().map(|ball| ball.position)
+    }
+
+    pub fn update(&mut self, dt: f32) -> PracticeResult {
+        self.events.clear();
+        self.phase = (self.phase + dt * 50.0) % 360.0;
+        self.machine_flash = (self.machine_flash - dt * 3.0).max(0.0);
+
+        self.particles.retain_mut(|p| {
+            p.update(dt);
+            p.is_alive()
+        });
+
+        if self.finished {
+            if is_key_pressed(KeyCode::R) {
+                self.restart();
+            } else if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Escape) {
+                return PracticeResult::Exit;
+            }
+            return PracticeResult::Continue;
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            self.finished = true;
+            return PracticeResult::Continue;
+        }
+
+        let bindings = self.paddle.bindings;
+        self.paddle.update(dt, None, bindings.keys_down(), bindings.depth_keys_down());
+
+        if self.stats.fired < self.settings.shots {
+            self.fire_timer -= dt;
+            if self.fire_timer <= 0.0 {
+                self.fire_timer = self.settings.interval;
+                self.fire();
+            }
+        }
+
+        let (target_top, target_bottom) = self.target_range();
+        let mut index = 0;
+        while index < self.balls.len() {
+            let ball = &mut self.balls[index];
+            let untouched = ball.last_touched.is_none();
+            if let Some(collision) = ball.update(dt, std::slice::from_ref(&self.paddle), [false, false, true, true]) {
+                self.particles.extend(create_particle_explosion(collision.position, collision.hue, self.effects.particles(10)));
+                self.events.emit(GameEvent::PaddleHit {
+                    side: Side::Left,
+                    position: collision.position,
+                    hue: collision.hue,
+                    speed: ball.velocity.length(),
+                    offset: self.paddle.hit_offset(collision.position),
+                });
+                if untouched {
+                    self.stats.returned += 1;
+                    self.stats.streak += 1;
+                    self.stats.best_streak = self.stats.best_streak.max(self.stats.streak);
+                    self.stats.return_speed_total += ball.velocity.length();
+                }
+            }
+
+            if let Some(side) = ball.wall_bounce {
+                self.events.emit(GameEvent::WallBounce {
+                    side,
+                    position: ball.position,
+                    hue: ball.hue,
+                    speed: ball.velocity.length(),
+                });
+            }
+
+            match ball.scored() {
+                Some(Side::Left) => {
+                    self.stats.missed += 1;
+                    self.stats.streak = 0;
+                    let exit = Vec2::new(0.0, ball.position.y);
+                    self.particles.extend(create_particle_explosion(exit, 0.0, self.effects.particles(8)));
+                    self.events.emit(GameEvent::BallOut { conceding: Side::Left, position: exit, hue: 0.0 });
+                    self.balls.remove(index);
+                }
+                Some(_) => {
+                    let on_target = (target_top..=target_bottom).contains(&ball.position.y);
+                    if on_target {
+                        self.stats.on_target += 1;
+                        let exit = Vec2::new(SCREEN_WIDTH, ball.position.y);
+                        self.particles.extend(create_particle_explosion(exit, 120.0, self.effects.particles(PARTICLE_COUNT)));
+                        self.events.emit(GameEvent::Score { scorer: Side::Left, server: Side::Right });
+                    }
+                    self.balls.remove(index);
+                }
+                None => index += 1,
+            }
+        }
+
+        if self.stats.fired >= self.settings.shots && self.balls.is_empty() {
+            self.finished = true;
+        }
+
+        PracticeResult::Continue
+    }
+
+    pub fn draw(&self) {
+        draw_background(self.phase);
+
+        let (target_top, target_bottom) = self.target_range();
+        let target_color = get_rainbow_color((self.phase + 120.0) % 360.0);
+        draw_rectangle(
+            SCREEN_WIDTH - 8.0,
+            target_top,
+            8.0,
+            target_bottom - target_top,
+            Color::new(target_color.r, target_color.g, target_color.b, 0.8),
+        );
+        draw_rectangle_lines(
+            SCREEN_WIDTH - 60.0,
+            target_top,
+            60.0,
+            target_bottom - target_top,
+            2.0,
+            Color::new(target_color.r, target_color.g, target_color.b, 0.3),
+        );
+
+        let machine = Vec2::new(SCREEN_WIDTH - PADDLE_MARGIN, self.machine_y);
+        let machine_color = get_rainbow_color((self.phase + 240.0) % 360.0);
+        draw_glow(machine, 20.0, machine_color, 0.5 + self.machine_flash);
+        draw_rectangle(machine.x - 12.0, machine.y - 20.0, 24.0, 40.0, Color::new(machine_color.r * 0.4, machine_color.g * 0.4, machine_color.b * 0.4, 0.9));
+        draw_rectangle_lines(machine.x - 12.0, machine.y - 20.0, 24.0, 40.0, 2.0, machine_color);
+        let barrel = machine + Vec2::from_angle(std::f32::consts::PI - self.machine_angle.to_radians()) * 28.0;
+        draw_line(machine.x, machine.y, barrel.x, barrel.y, 6.0, machine_color);
+
+        self.paddle.draw(self.phase);
+        for ball in &self.balls {
+            ball.draw(self.phase);
+        }
+        for particle in &self.particles {
+            particle.draw(self.phase);
+        }
+
+        let shown = self.stats.fired.min(self.settings.shots);
+        let hud = format!(
+            "{}  SHOT {}/{}  RETURNED {}  ON TARGET {}  STREAK {}",
+            self.settings.drill.label(),
+            shown,
+            self.settings.shots,
+            self.stats.returned,
+            self.stats.on_target,
+            self.stats.streak,
+        );
+        draw_centered_text(&hud, SCREEN_WIDTH / 2.0, 30.0, 20.0, get_rainbow_color((self.phase + 30.0) % 360.0));
+
+        if self.stats.fired == 0 {
+            let countdown = format!("{}", self.fire_timer.ceil() as u32);
+            let color = get_rainbow_color((self.phase + 240.0) % 360.0);
+            draw_centered_text(&countdown, SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0 - 60.0, 90.0, color);
+        }
+
+        if self.finished {
+            self.draw_summary();
+        }
+    }
+
+    fn draw_summary(&self) {
+        draw_rectangle(0.0, 0.0, SCREEN_WIDTH, SCREEN_HEIGHT, Color::new(0.0, 0.0, 0.0, 0.7));
+
+        let title = format!("{} DRILL COMPLETE", self.settings.drill.label());
+        let title_size = 44.0;
+        for offset in 0..6 {
+            let glow_size = (offset as f32) * 3.0;
+            let alpha = 0.4 / (offset as f32 + 1.0);
+            let glow_color = get_rainbow_color((self.phase + offset as f32 * 30.0) % 360.0);
+            draw_text_ex(
+                &title,
+                SCREEN_WIDTH / 2.0 - measure_text(&title, None, title_size as u16, 1.0).width / 2.0 + glow_size,
+                150.0 + glow_size,
+                TextParams {
+                    font: None,
+                    font_size: title_size as u16,
+                    color: Color::new(glow_color.r, glow_color.g, glow_color.b, alpha),
+                    ..Default::default()
+                },
+            );
+        }
+        draw_text_ex(
+            &title,
+            SCREEN_WIDTH / 2.0 - measure_text(&title, None, title_size as u16, 1.0).width / 2.0,
+            150.0,
+            TextParams {
+                font: None,
+                font_size: title_size as u16,
+                color: get_rainbow_color(self.phase),
+                ..Default::default()
+            },
+        );
+
+        let line_size = 26.0;
+        for (i, line) in self.stats.lines().iter().enumerate() {
+            draw_text_ex(
+                line,
+                SCREEN_WIDTH / 2.0 - measure_text(line, None, line_size as u16, 1.0).width / 2.0,
+                220.0 + i as f32 * 36.0,
+                TextParams {
+                    font: None,
+                    font_size: line_size as u16,
+                    color: get_rainbow_color((self.phase + 60.0 + i as f32 * 40.0) % 360.0),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let press_text = "R to run the drill again, ENTER to return to menu";
+        let press_size = 22.0;
+        draw_text_ex(
+            press_text,
+            SCREEN_WIDTH / 2.0 - measure_text(press_text, None, press_size as u16, 1.0).width / 2.0,
+            SCREEN_HEIGHT - 80.0,
+            TextParams {
+                font: None,
+                font_size: press_size as u16,
+                color: Color::new(0.8, 0.8, 0.8, 0.9),
+                ..Default::default()
+            },
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::consts::*;
+use crate::controls::KeyBindings;
+use crate::rules::GameMode;
+use crate::stats::MatchStats;
+use crate::storage;
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct LifetimeStats {
+    #[serde(default)]
+    pub matches: u32,
+    #[serde(default)]
+    pub wins: BTreeMap<String, u32>,
+    #[serde(default)]
+    pub best_rally: u32,
+    #[serde(default)]
+    pub fastest_ball: f32,
+}
+
+impl LifetimeStats {
+    pub fn total_wins(&self) -> u32 {
+        self.wins.values().sum()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("MATCHES: {}", self.matches),
+            format!("WINS: {}", self.total_wins()),
+        ];
+        for (mode, wins) in &self.wins {
+            lines.push(format!("  {}: {}", mode, wins));
+        }
+        lines.push(format!("BEST RALLY: {}", self.best_rally));
+        lines.push(format!("FASTEST BALL: {:.0}", self.fastest_ball));
+        lines
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub hue: f32,
+    #[serde(default)]
+    pub bindings: Option<KeyBindings>,
+    #[serde(default)]
+    pub stats: LifetimeStats,
+    #[serde(default)]
+    pub achievements: Vec<String>,
+}
+
+const COLOR_NAMES: [&str; 12] = [
+    "RED", "ORANGE", "YELLOW", "LIME", "GREEN", "MINT", "CYAN", "SKY", "BLUE", "VIOLET", "MAGENTA", "PINK",
+];
+
+impl Profile {
+    pub fn new(name: String, hue: f32) -> Self {
+        Self {
+            name,
+            hue,
+            bindings: None,
+            stats: LifetimeStats::default(),
+            achievements: Vec::new(),
+        }
+    }
+
+    pub fn color_name(&self) -> &'static str {
+        COLOR_NAMES[((self.hue / 30.0).round() as usize) % COLOR_NAMES.len()]
+    }
+
+    pub fn record_match(&mut self, mode: GameMode, won: bool, stats: &MatchStats) {
+        self.stats.matches += 1;
+        if won {
+            *self.stats.wins.entry(mode.label().to_string()).or_insert(0) += 1;
+        }
+        self.stats.best_rally = self.stats.best_rally.max(stats.longest_rally);
+        self.stats.fastest_ball = self.stats.fastest_ball.max(stats.top_speed);
+    }
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct Profiles {
+    #[serde(default, rename = "profile")]
+    pub entries: Vec<Profile>,
+}
+
+impl Profiles {
+    pub fn load() -> Self {
+        storage::load(PROFILE_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        storage::save(PROFILE_FILE, self)
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.entries.len() {
+            self.entries.remove(index);
+        }
+    }
+
+    pub fn create(&mut self) -> usize {
+        let hue = (self.entries.len() as f32 * 60.0 + 30.0) % 360.0;
+        self.entries.push(Profile::new(format!("PLAYER {}", self.entries.len() + 1), hue));
+        self.entries.len() - 1
+    }
+}
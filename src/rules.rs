@@ -0,0 +1,532 @@
+use macroquad::math::Vec2;
+use crate::consts::{DOUBLES_FORWARD_OFFSET, FOUR_PLAYER_LIVES, MULTI_BALL_COUNT, WIN_SCORE};
+use crate::powerups::PowerUpSet;
+use crate::practice::MachineSettings;
+use crate::ai::AiStyle;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Side {
+    pub const ALL: [Side; 4] = [Side::Left, Side::Right, Side::Top, Side::Bottom];
+
+    pub fn index(self) -> usize {
+        match self {
+            Side::Left => 0,
+            Side::Right => 1,
+            Side::Top => 2,
+            Side::Bottom => 3,
+        }
+    }
+
+    pub fn opponent(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+            Side::Top => Side::Bottom,
+            Side::Bottom => Side::Top,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Side::Left => "LEFT",
+            Side::Right => "RIGHT",
+            Side::Top => "TOP",
+            Side::Bottom => "BOTTOM",
+        }
+    }
+
+    pub fn hue(self) -> f32 {
+        match self {
+            Side::Left => 0.0,
+            Side::Right => 180.0,
+            Side::Top => 90.0,
+            Side::Bottom => 270.0,
+        }
+    }
+
+    pub fn is_horizontal(self) -> bool {
+        matches!(self, Side::Top | Side::Bottom)
+    }
+
+    pub fn normal(self) -> Vec2 {
+        match self {
+            Side::Left => Vec2::X,
+            Side::Right => -Vec2::X,
+            Side::Top => Vec2::Y,
+            Side::Bottom => -Vec2::Y,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Seat {
+    pub side: Side,
+    pub depth: f32,
+}
+
+impl Seat {
+    pub fn new(side: Side) -> Self {
+        Self { side, depth: 0.0 }
+    }
+
+    pub fn forward(side: Side) -> Self {
+        Self { side, depth: DOUBLES_FORWARD_OFFSET }
+    }
+
+    pub fn label(&self) -> String {
+        if self.depth > 0.0 {
+            format!("{} FRONT", self.side.label())
+        } else {
+            self.side.label().to_string()
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    Classic,
+    MultiBall,
+    FourPlayer,
+    Doubles,
+    Breakout,
+    Practice,
+    Survival,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 7] = [
+        GameMode::Classic,
+        GameMode::MultiBall,
+        GameMode::FourPlayer,
+        GameMode::Doubles,
+        GameMode::Breakout,
+        GameMode::Practice,
+        GameMode::Survival,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GameMode::Classic => "CLASSIC",
+            GameMode::MultiBall => "MULTI-BALL",
+            GameMode::FourPlayer => "FOUR PLAYER",
+            GameMode::Doubles => "DOUBLES 2V2",
+            GameMode::Breakout => "BREAKOUT",
+            GameMode::Practice => "PRACTICE",
+            GameMode::Survival => "SURVIVAL",
+        }
+    }
+
+    pub fn seats(self) -> Vec<Seat> {
+        match self {
+            GameMode::FourPlayer => Side::ALL.map(Seat::new).to_vec(),
+            GameMode::Doubles => vec![
+                Seat::new(Side::Left),
+                Seat::new(Side::Right),
+                Seat::forward(Side::Left),
+                Seat::forward(Side::Right),
+            ],
+            GameMode::Practice | GameMode::Survival => vec![Seat::new(Side::Left)],
+            GameMode::Classic | GameMode::MultiBall | GameMode::Breakout => {
+                vec![Seat::new(Side::Left), Seat::new(Side::Right)]
+            }
+        }
+    }
+
+    pub fn has_seat_setup(self) -> bool {
+        matches!(self, GameMode::FourPlayer | GameMode::Doubles)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PointEnd {
+    LastBallOut,
+    EveryBall,
+}
+
+impl PointEnd {
+    pub fn label(self) -> &'static str {
+        match self {
+            PointEnd::LastBallOut => "LAST BALL OUT",
+            PointEnd::EveryBall => "EVERY BALL SCORES",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MatchRules {
+    pub target_score: u32,
+    pub win_by_two: bool,
+    pub best_of_games: u32,
+    pub best_of_sets: u32,
+    pub serve_every: u32,
+    pub manual_serve: bool,
+    pub spin: bool,
+    pub power_ups: PowerUpSet,
+    pub mode: GameMode,
+    pub point_end: PointEnd,
+    pub lives: u32,
+    pub paddle_zone: f32,
+    pub machine: MachineSettings,
+    pub ai: AiStyle,
+}
+
+impl MatchRules {
+    pub fn classic() -> Self {
+        Self {
+            target_score: WIN_SCORE,
+            win_by_two: false,
+            best_of_games: 1,
+            best_of_sets: 1,
+            serve_every: 1,
+            manual_serve: false,
+            spin: false,
+            power_ups: PowerUpSet::none(),
+            mode: GameMode::Classic,
+            point_end: PointEnd::LastBallOut,
+            lives: FOUR_PLAYER_LIVES,
+            paddle_zone: 0.0,
+            machine: MachineSettings::standard(),
+            ai: AiStyle::standard(),
+        }
+    }
+
+    pub fn serve_balls(&self) -> usize {
+        match self.mode {
+            GameMode::MultiBall => MULTI_BALL_COUNT,
+            _ => 1,
+        }
+    }
+
+    pub fn presets() -> Vec<MatchRules> {
+        vec![
+            Self::classic(),
+            Self {
+                target_score: 11,
+                win_by_two: true,
+                serve_every: 2,
+                ..Self::classic()
+            },
+            Self {
+                target_score: 11,
+                win_by_two: true,
+                best_of_games: 3,
+                serve_every: 2,
+                ..Self::classic()
+            },
+            Self {
+                target_score: 5,
+                best_of_games: 3,
+                best_of_sets: 3,
+                ..Self::classic()
+            },
+            Self {
+                target_score: 21,
+                win_by_two: true,
+                serve_every: 5,
+                ..Self::classic()
+            },
+        ]
+    }
+
+    pub fn with_format(&self, preset: &MatchRules) -> MatchRules {
+        MatchRules {
+            target_score: preset.target_score,
+            win_by_two: preset.win_by_two,
+            best_of_games: preset.best_of_games,
+            best_of_sets: preset.best_of_sets,
+            serve_every: preset.serve_every,
+            ..*self
+        }
+    }
+
+    pub fn games_to_win(&self) -> u32 {
+        self.best_of_games / 2 + 1
+    }
+
+    pub fn sets_to_win(&self) -> u32 {
+        self.best_of_sets / 2 + 1
+    }
+
+    pub fn short_label(&self) -> String {
+        let mut text = format!("FIRST TO {}", self.target_score);
+        if self.win_by_two {
+            text.push_str(" DEUCE");
+        }
+        if self.best_of_games > 1 {
+            text.push_str(&format!(" BO{}", self.best_of_games));
+        }
+        if self.best_of_sets > 1 {
+            text.push_str(&format!("x{}", self.best_of_sets));
+        }
+        text
+    }
+
+    pub fn describe(&self) -> String {
+        if self.mode == GameMode::FourPlayer {
+            return format!("Last player standing, {} lives each", self.lives);
+        }
+        let mut text = format!("First to {}", self.target_score);
+        if self.win_by_two {
+            text.push_str(", win by 2");
+        }
+        if self.best_of_games > 1 {
+            text.push_str(&format!(", best of {} games", self.best_of_games));
+        }
+        if self.best_of_sets > 1 {
+            text.push_str(&format!(", best of {} sets", self.best_of_sets));
+        }
+        if self.best_of_games == 1 && self.best_of_sets == 1 {
+            text.push_str(" wins");
+        }
+        text
+    }
+
+    pub fn menu_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("SCORE TO {} TO WIN", self.target_score)];
+        if self.mode == GameMode::Practice {
+            return vec![
+                "BALL MACHINE PRACTICE".to_string(),
+                format!("DRILL: {}", self.machine.drill.label()),
+                format!("{} SHOTS EVERY {:.1}s", self.machine.shots, self.machine.interval),
+            ];
+        }
+        if self.mode == GameMode::Survival {
+            return vec![
+                "ENDLESS SURVIVAL".to_string(),
+                "THE BALL NEVER STOPS".to_string(),
+                "SPEEDING UP".to_string(),
+            ];
+        }
+        if self.mode == GameMode::FourPlayer {
+            lines = vec!["LAST ONE STANDING".to_string(), format!("{} LIVES EACH", self.lives)];
+        } else if self.win_by_two {
+            lines.push("WIN BY 2".to_string());
+        }
+        if self.best_of_games > 1 && self.mode != GameMode::FourPlayer {
+            lines.push(format!("BEST OF {} GAMES", self.best_of_games));
+        }
+        if self.best_of_sets > 1 && self.mode != GameMode::FourPlayer {
+            lines.push(format!("BEST OF {} SETS", self.best_of_sets));
+        }
+        if self.serve_every > 1 && self.mode != GameMode::FourPlayer {
+            lines.push(format!("SERVE SWAPS EVERY {}", self.serve_every));
+        }
+        if self.spin {
+            lines.push("BALL SPIN ON".to_string());
+        }
+        if self.mode == GameMode::MultiBall {
+            lines.push(format!("{} BALLS: {}", MULTI_BALL_COUNT, self.point_end.label()));
+        }
+        if self.mode == GameMode::Doubles {
+            lines.push("2V2: FRONT AND BACK PADDLES".to_string());
+        }
+        if self.mode == GameMode::Breakout {
+            lines.push("BRICKS SCORE 1 POINT".to_string());
+        }
+        if self.paddle_zone > 0.0 {
+            lines.push(format!("PADDLES STEP UP {}", self.paddle_zone));
+        }
+        if self.power_ups.count() > 0 {
+            lines.push(format!("{} POWER-UPS ON", self.power_ups.count()));
+        }
+        lines
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScoreOutcome {
+    Point,
+    Game(Side),
+    Set(Side),
+    Match(Side),
+}
+
+pub struct MatchScore {
+    pub rules: MatchRules,
+    pub points: [u32; 2],
+    pub games: [u32; 2],
+    pub sets: [u32; 2],
+}
+
+impl MatchScore {
+    pub fn new(rules: MatchRules) -> Self {
+        Self {
+            rules,
+            points: [0, 0],
+            games: [0, 0],
+            sets: [0, 0],
+        }
+    }
+
+    pub fn points_played(&self) -> u32 {
+        self.points[0] + self.points[1]
+    }
+
+    pub fn server(&self, first_server: Side) -> Side {
+        let turn = self.points_played() / self.rules.serve_every.max(1);
+        let games_played = self.games[0] + self.games[1] + self.sets[0] + self.sets[1];
+        if (turn + games_played).is_multiple_of(2) {
+            first_server
+        } else {
+            first_server.opponent()
+        }
+    }
+
+    pub fn award_point(&mut self, side: Side) -> ScoreOutcome {
+        debug_assert!(!side.is_horizontal(), "only the left and right sides keep a match score");
+        if side.is_horizontal() {
+            return ScoreOutcome::Point;
+        }
+        let (me, them) = (side.index(), side.opponent().index());
+        self.points[me] += 1;
+
+        let reached = self.points[me] >= self.rules.target_score;
+        let clear = !self.rules.win_by_two || self.points[me] >= self.points[them] + 2;
+        if !(reached && clear) {
+            return ScoreOutcome::Point;
+        }
+
+        self.games[me] += 1;
+        if self.games[me] < self.rules.games_to_win() {
+            self.points = [0, 0];
+            return ScoreOutcome::Game(side);
+        }
+
+        self.sets[me] += 1;
+        if self.sets[me] < self.rules.sets_to_win() {
+            self.points = [0, 0];
+            self.games = [0, 0];
+            return ScoreOutcome::Set(side);
+        }
+
+        ScoreOutcome::Match(side)
+    }
+
+    pub fn match_point(&self) -> Option<Side> {
+        [Side::Left, Side::Right].into_iter().find(|side| {
+            let (me, them) = (side.index(), side.opponent().index());
+            self.points[me] + 1 >= self.rules.target_score
+                && (!self.rules.win_by_two || self.points[me] > self.points[them])
+                && self.games[me] + 1 >= self.rules.games_to_win()
+                && self.sets[me] + 1 >= self.rules.sets_to_win()
+        })
+    }
+
+    pub fn is_deuce(&self) -> bool {
+        self.rules.win_by_two
+            && self.points[0] == self.points[1]
+            && self.points[0] + 1 >= self.rules.target_score
+    }
+
+    pub fn advantage(&self) -> Option<Side> {
+        if !self.rules.win_by_two || self.points[0] + 1 < self.rules.target_score || self.points[1] + 1 < self.rules.target_score {
+            return None;
+        }
+        match self.points[0] as i64 - self.points[1] as i64 {
+            1 => Some(Side::Left),
+            -1 => Some(Side::Right),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn winning_the_match_keeps_the_final_score() {
+        let mut score = MatchScore::new(MatchRules { target_score: 2, best_of_games: 3, ..MatchRules::classic() });
+        for side in [Side::Left, Side::Left, Side::Right, Side::Left] {
+            score.award_point(side);
+        }
+        assert_eq!((score.points, score.games), ([1, 1], [1, 0]));
+        assert_eq!(score.award_point(Side::Left), ScoreOutcome::Match(Side::Left));
+        assert_eq!(score.points, [2, 1]);
+        assert_eq!(score.games, [2, 0]);
+        assert_eq!(score.sets, [1, 0]);
+    }
+
+    #[test]
+    fn first_to_target_wins_without_deuce() {
+        let mut score = MatchScore::new(MatchRules { target_score: 3, ..MatchRules::classic() });
+        for side in [Side::Left, Side::Right, Side::Left, Side::Right] {
+            assert_eq!(score.award_point(side), ScoreOutcome::Point);
+        }
+        assert!(!score.is_deuce());
+        assert_eq!(score.advantage(), None);
+        assert_eq!(score.match_point(), Some(Side::Left));
+        assert_eq!(score.award_point(Side::Right), ScoreOutcome::Match(Side::Right));
+    }
+
+    #[test]
+    fn win_by_two_goes_through_deuce_and_advantage() {
+        let mut score = MatchScore::new(MatchRules { target_score: 3, win_by_two: true, ..MatchRules::classic() });
+        for side in [Side::Left, Side::Left, Side::Right, Side::Right] {
+            score.award_point(side);
+        }
+        assert!(score.is_deuce());
+        assert_eq!(score.match_point(), None);
+
+        assert_eq!(score.award_point(Side::Left), ScoreOutcome::Point);
+        assert_eq!(score.advantage(), Some(Side::Left));
+        assert_eq!(score.match_point(), Some(Side::Left));
+
+        assert_eq!(score.award_point(Side::Right), ScoreOutcome::Point);
+        assert!(score.is_deuce());
+        assert_eq!(score.advantage(), None);
+
+        score.award_point(Side::Right);
+        assert_eq!(score.advantage(), Some(Side::Right));
+        assert_eq!(score.award_point(Side::Right), ScoreOutcome::Match(Side::Right));
+        assert_eq!(score.points, [3, 5]);
+    }
+
+    #[test]
+    fn games_and_sets_reset_the_lower_tallies() {
+        let rules = MatchRules { target_score: 1, best_of_games: 3, best_of_sets: 3, ..MatchRules::classic() };
+        let mut score = MatchScore::new(rules);
+        assert_eq!(score.award_point(Side::Left), ScoreOutcome::Game(Side::Left));
+        assert_eq!((score.points, score.games), ([0, 0], [1, 0]));
+
+        assert_eq!(score.award_point(Side::Left), ScoreOutcome::Set(Side::Left));
+        assert_eq!((score.points, score.games, score.sets), ([0, 0], [0, 0], [1, 0]));
+
+        assert_eq!(score.award_point(Side::Right), ScoreOutcome::Game(Side::Right));
+        assert_eq!(score.award_point(Side::Left), ScoreOutcome::Game(Side::Left));
+        assert_eq!(score.match_point(), Some(Side::Left));
+        assert_eq!(score.award_point(Side::Left), ScoreOutcome::Match(Side::Left));
+        assert_eq!(score.sets, [2, 0]);
+    }
+
+    #[test]
+    fn serve_alternates_every_few_points_and_each_game() {
+        let rules = MatchRules { target_score: 3, best_of_games: 3, serve_every: 2, ..MatchRules::classic() };
+        let mut score = MatchScore::new(rules);
+        let mut servers = Vec::new();
+        for _ in 0..3 {
+            servers.push(score.server(Side::Left));
+            score.award_point(Side::Left);
+        }
+        assert_eq!(servers, [Side::Left, Side::Left, Side::Right]);
+        assert_eq!(score.games, [1, 0]);
+        assert_eq!(score.server(Side::Left), Side::Right);
+        assert_eq!(score.server(Side::Right), Side::Left);
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic(expected = "only the left and right sides"))]
+    fn top_and_bottom_never_score_a_point() {
+        let mut score = MatchScore::new(MatchRules::classic());
+        assert_eq!(score.award_point(Side::Top), ScoreOutcome::Point);
+        assert_eq!(score.award_point(Side::Bottom), ScoreOutcome::Point);
+        assert_eq!(score.points, [0, 0]);
+    }
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use crate::ai::{AiStyle, Difficulty};
+use crate::audio::AudioSettings;
+use crate::consts::*;
+use crate::controls::KeyBindings;
+use crate::effects::EffectSettings;
+use crate::rules::MatchRules;
+use crate::storage;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub audio: AudioSettings,
+    pub effects: EffectSettings,
+    pub difficulty: Difficulty,
+    pub win_score: u32,
+    pub fullscreen: bool,
+    pub bindings: [KeyBindings; 4],
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            audio: AudioSettings::default(),
+            effects: EffectSettings::default(),
+            difficulty: Difficulty::Normal,
+            win_score: WIN_SCORE,
+            fullscreen: false,
+            bindings: [0, 1, 2, 3].map(KeyBindings::for_seat),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        storage::load::<Settings>(SETTINGS_FILE).clamped()
+    }
+
+    fn clamped(mut self) -> Self {
+        self.win_score = self.win_score.clamp(1, MAX_WIN_SCORE);
+        self
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        storage::save(SETTINGS_FILE, self)
+    }
+
+    pub fn match_rules(&self) -> MatchRules {
+        MatchRules {
+            target_score: self.win_score,
+            ai: AiStyle { difficulty: self.difficulty, ..AiStyle::standard() },
+            ..MatchRules::classic()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loaded_win_score_stays_in_the_menu_range() {
+        let low = Settings { win_score: 0, ..Settings::default() }.clamped();
+        assert_eq!(low.match_rules().target_score, 1);
+        let high = Settings { win_score: MAX_WIN_SCORE + 10, ..Settings::default() }.clamped();
+        assert_eq!(high.match_rules().target_score, MAX_WIN_SCORE);
+    }
+}
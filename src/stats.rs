@@ -0,0 +1,248 @@
+use macroquad::prelude::*;
+use crate::effects::*;
+use crate::events::{EventListener, GameEvent};
+use crate::rules::Side;
+
+pub struct MatchStats {
+    pub sides: Vec<Side>,
+    pub hits: [u32; 4],
+    pub rally: u32,
+    pub longest_rally: u32,
+    pub top_speed: f32,
+    pub points_won: [u32; 4],
+    pub serve_points: [u32; 4],
+    pub point_times: Vec<f32>,
+    pub elapsed: f32,
+    speed_total: f32,
+    play_time: f32,
+    point_started: Option<f32>,
+    counts_lives: bool,
+    lives: [u32; 4],
+    timeline: Vec<(f32, [u32; 4])>,
+}
+
+impl MatchStats {
+    pub fn new(sides: Vec<Side>, counts_lives: bool, start: [u32; 4]) -> Self {
+        Self {
+            sides,
+            hits: [0; 4],
+            rally: 0,
+            longest_rally: 0,
+            top_speed: 0.0,
+            points_won: [0; 4],
+            serve_points: [0; 4],
+            point_times: Vec::new(),
+            elapsed: 0.0,
+            speed_total: 0.0,
+            play_time: 0.0,
+            point_started: None,
+            counts_lives,
+            lives: start,
+            timeline: vec![(0.0, start)],
+        }
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        self.elapsed += dt;
+    }
+
+    pub fn sample_speed(&mut self, dt: f32, speed: f32) {
+        self.speed_total += speed * dt;
+        self.play_time += dt;
+        self.top_speed = self.top_speed.max(speed);
+    }
+
+    pub fn average_speed(&self) -> f32 {
+        if self.play_time > 0.0 { self.speed_total / self.play_time } else { 0.0 }
+    }
+
+    fn record_serve(&mut self) {
+        self.rally = 0;
+        self.point_started = Some(self.elapsed);
+    }
+
+    fn record_hit(&mut self, side: Side) {
+        self.hits[side.index()] += 1;
+        self.rally += 1;
+        self.longest_rally = self.longest_rally.max(self.rally);
+    }
+
+    fn end_point(&mut self, tally: [u32; 4]) {
+        if let Some(started) = self.point_started.take() {
+            self.point_times.push(self.elapsed - started);
+        }
+        self.point_started = Some(self.elapsed);
+        self.timeline.push((self.elapsed, tally));
+    }
+
+    fn record_point(&mut self, scorer: Side, server: Side) {
+        self.points_won[scorer.index()] += 1;
+        if scorer == server {
+            self.serve_points[scorer.index()] += 1;
+        }
+        self.end_point(self.points_won);
+    }
+
+    fn record_life_lost(&mut self, side: Side, remaining: u32) {
+        self.lives[side.index()] = remaining;
+        self.end_point(self.lives);
+    }
+
+    fn per_side(&self, values: &[u32; 4]) -> String {
+        self.sides
+            .iter()
+            .map(|side| format!("{} {}", side.label(), values[side.index()]))
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let average_point = if self.point_times.is_empty() {
+            0.0
+        } else {
+            self.point_times.iter().sum::<f32>() / self.point_times.len() as f32
+        };
+        let longest_point = self.point_times.iter().copied().fold(0.0, f32::max);
+        let mut lines = vec![
+            format!("HITS: {}", self.per_side(&self.hits)),
+            format!("LONGEST RALLY: {}", self.longest_rally),
+            format!("AVG BALL SPEED: {:.0}", self.average_speed()),
+            format!("TOP BALL SPEED: {:.0}", self.top_speed),
+        ];
+        if !self.counts_lives {
+            lines.push(format!("WON ON SERVE: {}", self.per_side(&self.serve_points)));
+        }
+        lines.extend([
+            format!("AVG TIME PER POINT: {:.1}s", average_point),
+            format!("LONGEST POINT: {:.1}s", longest_point),
+            format!("MATCH TIME: {}:{:02}", self.elapsed as u32 / 60, self.elapsed as u32 % 60),
+        ]);
+        lines
+    }
+
+    pub fn draw_graph(&self, area: Rect, phase: f32) {
+        draw_rectangle(area.x, area.y, area.w, area.h, Color::new(0.0, 0.0, 0.0, 0.5));
+        let axis_color = Color::new(0.7, 0.7, 0.7, 0.8);
+        draw_line(area.x, area.y + area.h, area.x + area.w, area.y + area.h, 2.0, axis_color);
+        draw_line(area.x, area.y, area.x, area.y + area.h, 2.0, axis_color);
+        draw_text_ex(
+            if self.counts_lives { "LIVES OVER TIME" } else { "POINTS OVER TIME" },
+            area.x,
+            area.y - 8.0,
+            TextParams {
+                font: None,
+                font_size: 18,
+                color: axis_color,
+                ..Default::default()
+            },
+        );
+
+        let top = self
+            .timeline
+            .iter()
+            .flat_map(|(_, tally)| self.sides.iter().map(|side| tally[side.index()]))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let duration = self.elapsed.max(1.0);
+        let to_screen = |time: f32, value: u32| {
+            Vec2::new(
+                area.x + area.w * time / duration,
+                area.y + area.h - area.h * value as f32 / top as f32,
+            )
+        };
+
+        for (row, side) in self.sides.iter().enumerate() {
+            let color = get_rainbow_color((side.hue() + phase) % 360.0);
+            let mut previous = to_screen(0.0, self.timeline[0].1[side.index()]);
+            let steps = self.timeline.iter().skip(1).map(|(time, tally)| (*time, tally[side.index()]));
+            let last = self.timeline.last().map(|(_, tally)| tally[side.index()]).unwrap_or(0);
+            for (time, value) in steps.chain(std::iter::once((self.elapsed, last))) {
+                let next = to_screen(time, value);
+                let corner = Vec2::new(next.x, previous.y);
+                draw_line(previous.x, previous.y, corner.x, corner.y, 3.0, color);
+                draw_line(corner.x, corner.y, next.x, next.y, 3.0, color);
+                previous = next;
+            }
+
+            let text = format!("{} {}", side.label(), last);
+            draw_text_ex(
+                &text,
+                area.x + area.w + 8.0,
+                area.y + 16.0 + row as f32 * 20.0,
+                TextParams {
+                    font: None,
+                    font_size: 16,
+                    color,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+impl EventListener for MatchStats {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::Serve { .. } => self.record_serve(),
+            GameEvent::PaddleHit { side, .. } => self.record_hit(side),
+            GameEvent::Score { scorer, server } => self.record_point(scorer, server),
+            GameEvent::LifeLost { side, remaining } => self.record_life_lost(side, remaining),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(side: Side) -> GameEvent {
+        GameEvent::PaddleHit { side, position: Vec2::ZERO, hue: 0.0, speed: 300.0, offset: 0.0 }
+    }
+
+    #[test]
+    fn rallies_points_and_serves_add_up() {
+        let mut stats = MatchStats::new(vec![Side::Left, Side::Right], false, [0; 4]);
+        let events = [
+            GameEvent::Serve { server: Side::Left },
+            hit(Side::Right),
+            hit(Side::Left),
+            hit(Side::Right),
+            GameEvent::Score { scorer: Side::Left, server: Side::Left },
+            GameEvent::Serve { server: Side::Right },
+            hit(Side::Left),
+            GameEvent::Score { scorer: Side::Left, server: Side::Right },
+        ];
+        for event in &events {
+            stats.tick(0.5);
+            stats.on_event(event);
+        }
+
+        assert_eq!(stats.hits, [2, 2, 0, 0]);
+        assert_eq!(stats.longest_rally, 3);
+        assert_eq!(stats.rally, 1);
+        assert_eq!(stats.points_won, [2, 0, 0, 0]);
+        assert_eq!(stats.serve_points, [1, 0, 0, 0]);
+        assert_eq!(stats.point_times, [2.0, 1.0]);
+        assert_eq!(stats.lines()[0], "HITS: LEFT 2  RIGHT 2");
+    }
+
+    #[test]
+    fn average_speed_is_weighted_by_time() {
+        let mut stats = MatchStats::new(vec![Side::Left, Side::Right], false, [0; 4]);
+        assert_eq!(stats.average_speed(), 0.0);
+        stats.sample_speed(1.0, 300.0);
+        stats.sample_speed(3.0, 500.0);
+        assert_eq!(stats.average_speed(), 450.0);
+        assert_eq!(stats.top_speed, 500.0);
+    }
+
+    #[test]
+    fn four_player_matches_track_lives_instead_of_serves() {
+        let mut stats = MatchStats::new(Side::ALL.to_vec(), true, [3; 4]);
+        stats.on_event(&GameEvent::LifeLost { side: Side::Top, remaining: 2 });
+        assert_eq!(stats.timeline.last(), Some(&(0.0, [3, 3, 2, 3])));
+        assert!(!stats.lines().iter().any(|line| line.starts_with("WON ON SERVE")));
+    }
+}
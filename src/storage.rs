@@ -0,0 +1,50 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::consts::SAVE_DIR;
+
+pub fn save_path(file_name: &str) -> PathBuf {
+    PathBuf::from(SAVE_DIR).join(file_name)
+}
+
+pub fn load<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    let path = save_path(file_name);
+    let Ok(source) = std::fs::read_to_string(&path) else {
+        return T::default();
+    };
+    toml::from_str(&source).unwrap_or_else(|err| {
+        eprintln!("Ignoring {}: {}", path.display(), err);
+        T::default()
+    })
+}
+
+pub fn save<T: Serialize>(file_name: &str, value: &T) -> Result<(), String> {
+    let source = toml::to_string_pretty(value).map_err(|err| err.to_string())?;
+    let path = save_path(file_name);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    std::fs::write(path, source).map_err(|err| err.to_string())
+}
+
+pub fn save_bytes(file_name: &str, bytes: &[u8]) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(SAVE_DIR).map_err(|err| err.to_string())?;
+    let path = save_path(file_name);
+    std::fs::write(&path, bytes).map_err(|err| err.to_string())?;
+    Ok(path)
+}
+
+pub fn today() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,476 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::consts::*;
+use crate::effects::*;
+use crate::ball::Ball;
+use crate::paddle::Paddle;
+use crate::rules::{MatchRules, Side};
+use crate::settings::Settings;
+use crate::storage;
+use crate::events::{EventBus, GameEvent};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    #[serde(default)]
+    pub top_speed: f32,
+    pub date: String,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct HighScores {
+    #[serde(default, rename = "score")]
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        let mut scores: HighScores = storage::load(HIGH_SCORE_FILE);
+        scores.entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        scores.entries.truncate(HIGH_SCORE_COUNT);
+        scores
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        storage::save(HIGH_SCORE_FILE, self)
+    }
+
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < HIGH_SCORE_COUNT || self.entries.last().is_some_and(|last| score > last.score))
+    }
+
+    pub fn insert(&mut self, entry: HighScore) -> usize {
+        let rank = self.entries.iter().position(|known| entry.score > known.score).unwrap_or(self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(HIGH_SCORE_COUNT);
+        rank
+    }
+
+    pub fn draw_table(&self, top: f32, highlight: Option<usize>, phase: f32) {
+        let size = 20.0;
+        let columns = [SCREEN_WIDTH / 2.0 - 230.0, SCREEN_WIDTH / 2.0 - 190.0, SCREEN_WIDTH / 2.0 + 10.0, SCREEN_WIDTH / 2.0 + 90.0];
+        let header = ["#", "NAME", "RETURNS", "DATE"];
+        for (x, text) in columns.iter().zip(header) {
+            draw_text_ex(
+                text,
+                *x,
+                top,
+                TextParams {
+                    font: None,
+                    font_size: size as u16,
+                    color: Color::new(0.7, 0.7, 0.7, 0.9),
+                    ..Default::default()
+                },
+            );
+        }
+
+        if self.entries.is_empty() {
+            let empty = "NO SCORES YET";
+            draw_text_ex(
+                empty,
+                SCREEN_WIDTH / 2.0 - measure_text(empty, None, size as u16, 1.0).width / 2.0,
+                top + 40.0,
+                TextParams {
+                    font: None,
+                    font_size: size as u16,
+                    color: get_rainbow_color(phase),
+                    ..Default::default()
+                },
+            );
+        }
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let y = top + 26.0 + i as f32 * 22.0;
+            let color = if Some(i) == highlight {
+                get_rainbow_color((phase * 3.0) % 360.0)
+            } else {
+                get_rainbow_color((phase + i as f32 * 30.0) % 360.0)
+            };
+            let cells = [format!("{}", i + 1), entry.name.clone(), format!("{}", entry.score), entry.date.clone()];
+            for (x, text) in columns.iter().zip(cells.iter()) {
+                draw_text_ex(
+                    text,
+                    *x,
+                    y,
+                    TextParams {
+                        font: None,
+                        font_size: size as u16,
+                        color,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SurvivalState {
+    Countdown,
+    Playing,
+    EnteringName,
+    Finished,
+}
+
+pub enum SurvivalResult {
+    Continue,
+    Exit,
+}
+
+pub struct SurvivalSession {
+    paddle: Paddle,
+    ball: Ball,
+    state: SurvivalState,
+    countdown: f32,
+    returns: u32,
+    top_speed: f32,
+    particles: Vec<Particle>,
+    effects: EffectSettings,
+    phase: f32,
+    screen_shake: f32,
+    high_scores: HighScores,
+    name: String,
+    rank: Option<usize>,
+    status: String,
+    events: EventBus,
+}
+
+impl SurvivalSession {
+    pub fn new(rules: MatchRules, settings: &Settings) -> Self {
+        let mut paddle = Paddle::new(Side::Left, 0.0, false, settings.bindings[0]);
+        paddle.set_zone_depth(rules.paddle_zone);
+        let mut ball = Ball::new();
+        ball.spin_enabled = rules.spin;
+        ball.speed_cap = f32::INFINITY;
+        let high_scores = HighScores::load();
+        let name = high_scores.entries.first().map(|entry| entry.name.clone()).unwrap_or_default();
+
+        Self {
+            paddle,
+            ball,
+            state: SurvivalState::Countdown,
+            countdown: SERVE_COUNTDOWN,
+            returns: 0,
+            top_speed: 0.0,
+            particles: Vec::new(),
+            effects: settings.effects,
+            phase: 0.0,
+            screen_shake: 0.0,
+            high_scores,
+            name,
+            rank: None,
+            status: String::new(),
+            events: EventBus::default(),
+        }
+    }
+
+    fn restart(&mut self) {
+        self.ball.reset();
+        self.ball.speed = BALL_INITIAL_SPEED;
+        self.state = SurvivalState::Countdown;
+        self.countdown = SERVE_COUNTDOWN;
+        self.returns = 0;
+        self.top_speed = 0.0;
+        self.rank = None;
+        self.status.clear();
+    }
+
+    fn end_run(&mut self) {
+        self.screen_shake = self.effects.shake(0.6);
+        self.state = if self.high_scores.qualifies(self.returns) {
+            while get_char_pressed().is_some() {}
+            SurvivalState::EnteringName
+        } else {
+            SurvivalState::Finished
+        };
+    }
+
+    fn update_name_entry(&mut self) {
+        while let Some(c) = get_char_pressed() {
+            if (c.is_ascii_alphanumeric() || c == ' ') && self.name.len() < 12 {
+                self.name.push(c.to_ascii_uppercase());
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.name.pop();
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            let name = if self.name.trim().is_empty() { "PLAYER".to_string() } else { self.name.trim().to_string() };
+            self.rank = Some(self.high_scores.insert(HighScore {
+                name,
+                score: self.returns,
+                top_speed: self.top_speed,
+                date: storage::today(),
+            }));
+            if let Err(err) = self.high_scores.save() {
+                self.status = format!("Could not save high scores: {}", err);
+            }
+            self.state = SurvivalState::Finished;
+        }
+    }
+
+    fn update_ball(&mut self, dt: f32) {
+        self.ball.speed += SURVIVAL_ACCELERATION * dt;
+        self.ball.velocity = self.ball.velocity.normalize_or_zero() * self.ball.speed.max(self.ball.velocity.length());
+
+        let steps = ((self.ball.velocity.length() * dt) / (BALL_SIZE / 2.0)).ceil().max(1.0);
+        let step_dt = dt / steps;
+        for _ in 0..steps as usize {
+            let paddles = std::slice::from_ref(&self.paddle);
+            if let Some(collision) = self.ball.update(step_dt, paddles, [false, true, true, true]) {
+                self.returns += 1;
+                self.screen_shake = self.effects.shake(0.3);
+                self.particles.extend(create_particle_explosion(collision.position, collision.hue, self.effects.particles(10)));
+                self.events.emit(GameEvent::PaddleHit {
+                    side: Side::Left,
+                    position: collision.position,
+                    hue: collision.hue,
+                    speed: self.ball.velocity.length(),
+                    offset: self.paddle.hit_offset(collision.position),
+                });
+            }
+            if let Some(side) = self.ball.wall_bounce {
+                self.events.emit(GameEvent::WallBounce {
+                    side,
+                    position: self.ball.position,
+                    hue: self.ball.hue,
+                    speed: self.ball.velocity.length(),
+                });
+            }
+            if self.ball.scored().is_some() {
+                break;
+            }
+        }
+        self.top_speed = self.top_speed.max(self.ball.velocity.length());
+    }
+
+    pub fn events(&self) -> &[GameEvent] {
+        self.events.events()
+    }
+
+    pub fn ball_position(&self) -> Option<Vec2> {
+        (self.state == SurvivalState::Playing).then_some(self.ball.position)
+    }
+
+    pub fn update(&mut self, dt: f32) -> SurvivalResult {
+        self.events.clear();
+        self.phase = (self.phase + dt * 50.0) % 360.0;
+        self.screen_shake = (self.screen_shake - dt * 5.0).max(0.0);
+        self.particles.retain_mut(|p| {
+            p.update(dt);
+            p.is_alive()
+        });
+
+        match self.state {
+            SurvivalState::EnteringName => {
+                self.update_name_entry();
+                return SurvivalResult::Continue;
+            }
+            SurvivalState::Finished => {
+                if is_key_pressed(KeyCode::R) {
+                    self.restart();
+                } else if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Escape) {
+                    return SurvivalResult::Exit;
+                }
+                return SurvivalResult::Continue;
+            }
+            SurvivalState::Countdown | SurvivalState::Playing => {}
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            return SurvivalResult::Exit;
+        }
+
+        let bindings = self.paddle.bindings;
+        self.paddle.update(dt, None, bindings.keys_down(), bindings.depth_keys_down());
+
+        if self.state == SurvivalState::Countdown {
+            self.countdown -= dt;
+            self.ball.hold(dt, Vec2::new(SCREEN_WIDTH * 0.75, SCREEN_HEIGHT / 2.0));
+            if self.countdown <= 0.0 {
+                let angle = macroquad::rand::gen_range(-30.0_f32, 30.0);
+                self.ball.launch(-Vec2::X, angle.to_radians());
+                self.events.emit(GameEvent::Serve { server: Side::Right });
+                self.state = SurvivalState::Playing;
+            }
+            return SurvivalResult::Continue;
+        }
+
+        self.update_ball(dt);
+
+        if self.ball.scored().is_some() {
+            let exit = Vec2::new(0.0, self.ball.position.y);
+            self.particles.extend(create_particle_explosion(exit, self.ball.hue, self.effects.particles(PARTICLE_COUNT)));
+            self.events.emit(GameEvent::BallOut { conceding: Side::Left, position: exit, hue: self.ball.hue });
+            self.events.emit(GameEvent::LifeLost { side: Side::Left, remaining: 0 });
+            self.end_run();
+        }
+
+        SurvivalResult::Continue
+    }
+
+    pub fn draw(&self) {
+        set_shake_camera(shake_offset(self.screen_shake));
+        draw_background(self.phase);
+
+        let wall_color = get_rainbow_color((self.phase + 200.0) % 360.0);
+        draw_glow(Vec2::new(SCREEN_WIDTH, SCREEN_HEIGHT / 2.0), 30.0, wall_color, 0.4);
+        draw_rectangle(SCREEN_WIDTH - 10.0, 0.0, 10.0, SCREEN_HEIGHT, wall_color);
+        draw_rectangle(0.0, 0.0, SCREEN_WIDTH, 5.0, get_rainbow_color(self.phase));
+        draw_rectangle(0.0, SCREEN_HEIGHT - 5.0, SCREEN_WIDTH, 5.0, get_rainbow_color((self.phase + 60.0) % 360.0));
+
+        self.paddle.draw(self.phase);
+        self.ball.draw(self.phase);
+        for particle in &self.particles {
+            particle.draw(self.phase);
+        }
+
+        let score_text = format!("{}", self.returns);
+        draw_centered_text(&score_text, SCREEN_WIDTH / 2.0, 60.0, 60.0, get_rainbow_color((self.phase + 30.0) % 360.0));
+
+        let best = self.high_scores.entries.first().map(|entry| entry.score).unwrap_or(0);
+        let info = format!("SPEED {:.0}   BEST {}", self.ball.velocity.length(), best.max(self.returns));
+        draw_centered_text(&info, SCREEN_WIDTH / 2.0, 85.0, 18.0, Color::new(0.8, 0.8, 0.8, 0.8));
+
+        if self.state == SurvivalState::Countdown {
+            let countdown = format!("{}", self.countdown.ceil() as u32);
+            let color = get_rainbow_color((self.phase + 240.0) % 360.0);
+            draw_centered_text(&countdown, SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0 - 60.0, 90.0, color);
+        }
+
+        set_screen_camera();
+
+        if matches!(self.state, SurvivalState::EnteringName | SurvivalState::Finished) {
+            self.draw_game_over();
+        }
+    }
+
+    fn draw_game_over(&self) {
+        draw_rectangle(0.0, 0.0, SCREEN_WIDTH, SCREEN_HEIGHT, Color::new(0.0, 0.0, 0.0, 0.75));
+
+        let title = format!("{} RETURNS", self.returns);
+        let title_size = 50.0;
+        for offset in 0..6 {
+            let glow_size = (offset as f32) * 3.0;
+            let alpha = 0.4 / (offset as f32 + 1.0);
+            let glow_color = get_rainbow_color((self.phase + offset as f32 * 30.0) % 360.0);
+            draw_text_ex(
+                &title,
+                SCREEN_WIDTH / 2.0 - measure_text(&title, None, title_size as u16, 1.0).width / 2.0 + glow_size,
+                110.0 + glow_size,
+                TextParams {
+                    font: None,
+                    font_size: title_size as u16,
+                    color: Color::new(glow_color.r, glow_color.g, glow_color.b, alpha),
+                    ..Default::default()
+                },
+            );
+        }
+        draw_text_ex(
+            &title,
+            SCREEN_WIDTH / 2.0 - measure_text(&title, None, title_size as u16, 1.0).width / 2.0,
+            110.0,
+            TextParams {
+                font: None,
+                font_size: title_size as u16,
+                color: get_rainbow_color(self.phase),
+                ..Default::default()
+            },
+        );
+
+        let (prompt, prompt_color) = if self.state == SurvivalState::EnteringName {
+            (format!("NEW HIGH SCORE! NAME: {}_", self.name), get_rainbow_color((self.phase * 3.0) % 360.0))
+        } else {
+            (format!("TOP SPEED {:.0}", self.top_speed), Color::new(0.8, 0.8, 0.8, 0.9))
+        };
+        let prompt_size = 26.0;
+        draw_text_ex(
+            &prompt,
+            SCREEN_WIDTH / 2.0 - measure_text(&prompt, None, prompt_size as u16, 1.0).width / 2.0,
+            160.0,
+            TextParams {
+                font: None,
+                font_size: prompt_size as u16,
+                color: prompt_color,
+                ..Default::default()
+            },
+        );
+
+        self.high_scores.draw_table(210.0, self.rank, self.phase);
+
+        let press_text = if self.state == SurvivalState::EnteringName {
+            "Type your name and press ENTER"
+        } else {
+            "R to try again, ENTER to return to menu"
+        };
+        let press_size = 22.0;
+        draw_text_ex(
+            press_text,
+            SCREEN_WIDTH / 2.0 - measure_text(press_text, None, press_size as u16, 1.0).width / 2.0,
+            SCREEN_HEIGHT - 70.0,
+            TextParams {
+                font: None,
+                font_size: press_size as u16,
+                color: Color::new(0.8, 0.8, 0.8, 0.9),
+                ..Default::default()
+            },
+        );
+
+        if !self.status.is_empty() {
+            let status_size = 16.0;
+            draw_text_ex(
+                &self.status,
+                SCREEN_WIDTH / 2.0 - measure_text(&self.status, None, status_size as u16, 1.0).width / 2.0,
+                SCREEN_HEIGHT - 40.0,
+                TextParams {
+                    font: None,
+                    font_size: status_size as u16,
+                    color: Color::new(1.0, 0.4, 0.4, 0.9),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u32) -> HighScore {
+        HighScore { name: name.to_string(), score, top_speed: 0.0, date: "2026-01-01".to_string() }
+    }
+
+    #[test]
+    fn any_positive_run_qualifies_until_the_table_is_full() {
+        let mut scores = HighScores::default();
+        assert!(!scores.qualifies(0));
+        assert!(scores.qualifies(1));
+
+        for score in 0..HIGH_SCORE_COUNT as u32 {
+            scores.insert(entry("FILL", 10 + score));
+        }
+        assert_eq!(scores.entries.len(), HIGH_SCORE_COUNT);
+        assert!(!scores.qualifies(10));
+        assert!(scores.qualifies(11));
+    }
+
+    #[test]
+    fn inserting_ranks_by_score_after_equal_scores_and_drops_the_last() {
+        let mut scores = HighScores::default();
+        assert_eq!(scores.insert(entry("A", 5)), 0);
+        assert_eq!(scores.insert(entry("B", 9)), 0);
+        assert_eq!(scores.insert(entry("C", 5)), 2);
+        let names: Vec<&str> = scores.entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["B", "A", "C"]);
+
+        for _ in 0..HIGH_SCORE_COUNT {
+            scores.insert(entry("D", 7));
+        }
+        assert_eq!(scores.entries.len(), HIGH_SCORE_COUNT);
+        assert_eq!(scores.entries[0].name, "B");
+        assert!(scores.entries[1..].iter().all(|entry| entry.score == 7));
+    }
+}
@@ -0,0 +1,215 @@
+use crate::consts::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Waveform {
+    Square(f32),
+    Triangle,
+    Sine,
+    Saw,
+    Noise,
+}
+
+impl Waveform {
+    fn sample(self, phase: f32, noise: &mut u32) -> f32 {
+        match self {
+            Waveform::Square(duty) => if phase < duty { 1.0 } else { -1.0 },
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Sine => (phase * std::f32::consts::TAU).sin(),
+            Waveform::Saw => phase * 2.0 - 1.0,
+            Waveform::Noise => {
+                *noise ^= *noise << 13;
+                *noise ^= *noise >> 17;
+                *noise ^= *noise << 5;
+                *noise as f32 / u32::MAX as f32 * 2.0 - 1.0
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Envelope {
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+}
+
+impl Envelope {
+    pub const PLUCK: Envelope = Envelope { attack: 0.002, decay: 0.06, sustain: 0.3, release: 0.05 };
+    pub const CLICK: Envelope = Envelope { attack: 0.001, decay: 0.02, sustain: 0.0, release: 0.01 };
+    pub const NOTE: Envelope = Envelope { attack: 0.005, decay: 0.08, sustain: 0.6, release: 0.08 };
+    pub const HOLD: Envelope = Envelope { attack: 0.0, decay: 0.0, sustain: 1.0, release: 0.0 };
+
+    pub fn level(&self, time: f32, length: f32) -> f32 {
+        let held = if time < self.attack {
+            time / self.attack
+        } else if time < self.attack + self.decay {
+            1.0 - (1.0 - self.sustain) * (time - self.attack) / self.decay
+        } else {
+            self.sustain
+        };
+        if time < length {
+            return held;
+        }
+        let gate_level = self.level(length, f32::INFINITY);
+        (gate_level * (1.0 - (time - length) / self.release)).max(0.0)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Tone {
+    pub waveform: Waveform,
+    pub frequency: f32,
+    pub slide: f32,
+    pub start: f32,
+    pub length: f32,
+    pub volume: f32,
+    pub envelope: Envelope,
+}
+
+impl Tone {
+    pub fn new(waveform: Waveform, frequency: f32, length: f32) -> Self {
+        Self {
+            waveform,
+            frequency,
+            slide: 1.0,
+            start: 0.0,
+            length,
+            volume: 0.5,
+            envelope: Envelope::PLUCK,
+        }
+    }
+
+    pub fn end(&self) -> f32 {
+        self.start + self.length + self.envelope.release
+    }
+
+    fn frequency_at(&self, time: f32) -> f32 {
+        self.frequency * self.slide.powf(time / (self.length + self.envelope.release))
+    }
+}
+
+pub fn transpose(frequency: f32, semitones: f32) -> f32 {
+    frequency * 2.0_f32.powf(semitones / 12.0)
+}
+
+pub fn render(tones: &[Tone]) -> Vec<f32> {
+    let duration = tones.iter().map(Tone::end).fold(0.0, f32::max);
+    let mut samples = vec![0.0; (duration * SYNTH_SAMPLE_RATE as f32).ceil() as usize];
+    for tone in tones {
+        mix_tone(&mut samples, tone);
+    }
+    for sample in &mut samples {
+        *sample = sample.clamp(-1.0, 1.0);
+    }
+    samples
+}
+
+pub fn mix_tone(samples: &mut [f32], tone: &Tone) {
+    let rate = SYNTH_SAMPLE_RATE as f32;
+    let first = (tone.start * rate) as usize;
+    let count = ((tone.length + tone.envelope.release) * rate) as usize;
+    let mut phase = 0.0;
+    let mut noise = 0x9e37_79b9_u32 ^ tone.frequency.to_bits();
+    for (i, sample) in samples.iter_mut().skip(first).take(count).enumerate() {
+        let time = i as f32 / rate;
+        *sample += tone.waveform.sample(phase, &mut noise) * tone.envelope.level(time, tone.length) * tone.volume;
+        phase = (phase + tone.frequency_at(time) / rate).fract();
+    }
+}
+
+pub fn pan_stereo(samples: &[f32], pan: f32) -> Vec<f32> {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * std::f32::consts::FRAC_PI_4;
+    let left = angle.cos() * std::f32::consts::SQRT_2;
+    let right = angle.sin() * std::f32::consts::SQRT_2;
+    samples.iter().flat_map(|sample| [sample * left, sample * right]).collect()
+}
+
+pub fn encode_wav(samples: &[f32], channels: u16) -> Vec<u8> {
+    let data_size = samples.len() as u32 * 2;
+    let byte_rate = SYNTH_SAMPLE_RATE * channels as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_size as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16_u32.to_le_bytes());
+    bytes.extend_from_slice(&1_u16.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&SYNTH_SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&byte_rate.to_le_bytes());
+    bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+    bytes.extend_from_slice(&16_u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_size.to_le_bytes());
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn render_covers_the_last_tone_and_clamps_samples() {
+        let tones = [
+            Tone { volume: 2.0, envelope: Envelope::HOLD, ..Tone::new(Waveform::Square(0.5), 100.0, 0.1) },
+            Tone { start: 0.2, ..Tone::new(Waveform::Sine, 440.0, 0.1) },
+        ];
+        let samples = render(&tones);
+        let expected = ((0.2 + 0.1 + Envelope::PLUCK.release) * SYNTH_SAMPLE_RATE as f32).ceil() as usize;
+        assert_eq!(samples.len(), expected);
+        assert!(samples.iter().all(|sample| (-1.0..=1.0).contains(sample)));
+        assert_eq!(samples[10], 1.0);
+    }
+
+    #[test]
+    fn envelope_follows_attack_decay_and_release() {
+        let envelope = Envelope { attack: 0.1, decay: 0.2, sustain: 0.5, release: 0.4 };
+        assert!(close(envelope.level(0.0, 1.0), 0.0));
+        assert!(close(envelope.level(0.05, 1.0), 0.5));
+        assert!(close(envelope.level(0.2, 1.0), 0.75));
+        assert!(close(envelope.level(0.5, 1.0), 0.5));
+        assert!(close(envelope.level(1.2, 1.0), 0.25));
+        assert!(close(envelope.level(1.5, 1.0), 0.0));
+        assert!(close(envelope.level(0.25, 0.05), 0.25));
+    }
+
+    #[test]
+    fn pan_stereo_splits_gain_between_channels() {
+        let hard_left = pan_stereo(&[0.5], -1.0);
+        assert!(close(hard_left[0], 0.5 * std::f32::consts::SQRT_2) && close(hard_left[1], 0.0));
+        let hard_right = pan_stereo(&[0.5], 1.0);
+        assert!(close(hard_right[0], 0.0) && close(hard_right[1], 0.5 * std::f32::consts::SQRT_2));
+        let centre = pan_stereo(&[0.5, -0.25], 0.0);
+        assert_eq!(centre.len(), 4);
+        assert!(close(centre[0], 0.5) && close(centre[1], 0.5) && close(centre[3], -0.25));
+    }
+
+    #[test]
+    fn encode_wav_writes_a_pcm_header_and_data() {
+        let wav = encode_wav(&[0.0, 1.0, -1.0, 0.5], 2);
+        let u16_at = |offset: usize| u16::from_le_bytes([wav[offset], wav[offset + 1]]);
+        let u32_at = |offset: usize| u32::from_le_bytes(wav[offset..offset + 4].try_into().unwrap());
+        assert_eq!(wav.len(), 44 + 8);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32_at(4), 36 + 8);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u16_at(20), 1);
+        assert_eq!(u16_at(22), 2);
+        assert_eq!(u32_at(24), SYNTH_SAMPLE_RATE);
+        assert_eq!(u32_at(28), SYNTH_SAMPLE_RATE * 4);
+        assert_eq!(u16_at(32), 4);
+        assert_eq!(u16_at(34), 16);
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(u32_at(40), 8);
+        assert_eq!(i16::from_le_bytes([wav[46], wav[47]]), i16::MAX);
+        assert_eq!(i16::from_le_bytes([wav[48], wav[49]]), -i16::MAX);
+    }
+}